@@ -0,0 +1,104 @@
+use std::fmt::{Display, Error as FmtError, Formatter};
+
+use voile_util::{meta::MI, uid::DBI};
+
+use crate::syntax::{
+    abs::Abs,
+    core::{Let, Tele, Term},
+};
+
+/// How the two sides of a constraint are compared.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Monad.Base.html#Comparison).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// Conversion check.
+    Eq,
+    /// Subtyping, used for the universes of the solutions of metas.
+    Leq,
+}
+
+/// A problem that is postponed because it's blocked on a meta.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Monad.Base.html#Constraint).
+#[derive(Debug, Clone)]
+pub enum Problem {
+    /// Compare the two terms.
+    Compare(Comparison, Term, Term),
+    /// Check the expression against the type, which is blocked.
+    /// The last one is the meta standing for the checked term.
+    Check(Abs, Term, Term),
+    /// Eliminate `head` of type `ty`, which is blocked, by `args`.
+    /// `fun` is the head expression, `term` and `term_ty` are the metas
+    /// standing for the result and its type.
+    Elims {
+        head: Term,
+        ty: Term,
+        fun: Box<Abs>,
+        args: Vec<Abs>,
+        term: Term,
+        term_ty: Term,
+    },
+}
+
+/// A postponed problem, together with the context it's postponed in.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Monad.Base.html#ProblemConstraint).
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub problem: Problem,
+    /// The meta that should be solved before trying again.
+    pub blocker: MI,
+    /// Local typing context when the constraint is postponed.
+    pub gamma: Tele,
+    /// Local let bindings when the constraint is postponed.
+    pub lets: Vec<(usize, Let)>,
+    /// Conversion check depth when the constraint is postponed.
+    pub unify_depth: DBI,
+}
+
+impl Constraint {
+    pub fn new(
+        problem: Problem,
+        blocker: MI,
+        gamma: Tele,
+        lets: Vec<(usize, Let)>,
+        unify_depth: DBI,
+    ) -> Self {
+        Self {
+            problem,
+            blocker,
+            gamma,
+            lets,
+            unify_depth,
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match self {
+            Comparison::Eq => f.write_str("="),
+            Comparison::Leq => f.write_str("<:"),
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match self {
+            Problem::Compare(cmp, left, right) => write!(f, "`{}` {} `{}`", left, cmp, right),
+            Problem::Check(abs, ty, _) => write!(f, "`{}` : `{}`", abs, ty),
+            Problem::Elims { head, ty, args, .. } => {
+                write!(f, "`{}", head)?;
+                for arg in args {
+                    write!(f, " {}", arg)?;
+                }
+                write!(f, "` where `{}` : `{}`", head, ty)
+            }
+        }
+    }
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        write!(f, "{} (blocked on meta {})", self.problem, self.blocker.0)
+    }
+}
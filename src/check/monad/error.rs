@@ -1,16 +1,18 @@
 use std::fmt::{Display, Error as FmtError, Formatter};
 
 use voile_util::{
-    level::Level,
     loc::{Ident, Loc, ToLoc},
     meta::MI,
 };
 
 use crate::{
-    check::pats::Blocked,
+    check::{
+        monad::{Constraint, MetaInfo, MetaOrigin},
+        pats::Blocked,
+    },
     syntax::{
-        abs::{Abs, AbsCopat},
-        core::{Elim, Term, Val},
+        abs::Abs,
+        core::{Elim, Level, Term, Val},
     },
 };
 
@@ -28,13 +30,49 @@ pub enum TCE {
     NotTerm(String),
     NotData(Box<Val>),
     NotCodata(Box<Val>),
+    NotId(Box<Val>),
+    NotUniverse(Box<Val>),
+    /// The datatype, the type it occurs non-positively in, and the location
+    /// of the constructor parameter.
+    NotPositive(Ident, Box<Term>, Loc),
+    /// The record constructor, and the field whose type mentions `self`
+    /// other than projecting the fields before it.
+    NotRecordCons(Ident, Ident),
 
     // === Split* === //
     SplitOnNonVar(Box<Term>, Box<Term>),
+    /// An absurd pattern on a type which is possibly inhabited,
+    /// with the type printed and the location of the clause.
+    NotEmpty(String, Loc),
+    /// A clause with absurd patterns has a body.
+    AbsurdBody(Loc),
+    /// A clause without absurd patterns has no body.
+    MissingBody(Loc),
+    /// The function and its missing cases.
+    MissingClauses(Ident, Vec<Vec<String>>),
+
+    // === Unify* === //
+    /// Different constructors.
+    UnifyConflict(Box<Term>, Box<Term>),
+    /// A variable occurs in the constructor application it should equal.
+    UnifyCycle(Box<Term>, Box<Term>),
+    /// None of the unification rules apply.
+    UnifyStuck(Box<Term>, Box<Term>),
+    /// Deleting a reflexive equation needs K. The two sides and their type
+    /// are printed, with the location of the clause (or the function).
+    UnifyReflexive(String, String, String, Loc),
 
     // === Meta* === //
     MetaRecursion(MI),
-    MetaUnsolved(MI),
+    /// The meta and its information.
+    MetaUnsolved(MI, Box<MetaInfo<Term>>),
+    /// The meta application is not applied to distinct variables,
+    /// and the term it should equal.
+    MetaNotPattern(Box<Term>, Box<Term>),
+    /// The solution mentions variables that are not in the scope of the meta.
+    MetaScope(MI, Box<Term>),
+    /// Postponed constraints that are still blocked at the end of a definition.
+    UnsolvedConstraints(Vec<Constraint>),
 
     // === Different* === //
     DifferentLevel(Loc, Level, Level),
@@ -42,11 +80,30 @@ pub enum TCE {
     DifferentTerm(Box<Term>, Box<Term>),
     DifferentElim(Box<Elim>, Box<Elim>),
     DifferentName(Ident, Ident),
+    /// Constructor, datatype.
+    DifferentConsData(Ident, Ident),
 
     // === Misc === //
     Blocked(Box<Blocked<Term>>),
     NoSuchProj(String),
-    CantElim(AbsCopat),
+    /// More patterns than the parameters of the type,
+    /// with the type printed and the location of the clause.
+    CantElim(String, Loc),
+    /// An implicit pattern or argument with no implicit parameter to match,
+    /// with the name of the parameter if given.
+    UnexpectedImplicit(Option<Ident>),
+    /// Constructor, expected number of arguments, actual number of arguments.
+    ConsArity(Ident, usize, usize),
+    /// Constructor, and its return type if it's not the datatype applied to
+    /// the parameters, or nothing if it's missing.
+    ConsReturn(Ident, Option<Box<Term>>),
+    /// A datatype whose universe depends on its indices.
+    DataSortIndexed(Ident),
+    /// The function and the recursive call path (with the clause locations)
+    /// that fails to decrease.
+    NonTerminating(Ident, Vec<(Loc, Term)>),
+    /// The function, the corecursive call and the location of the clause.
+    NotGuarded(Ident, Box<Term>, Loc),
 }
 
 impl TCE {
@@ -62,6 +119,26 @@ impl TCE {
         TCE::NotData(Box::new(val))
     }
 
+    pub fn not_id(val: Val) -> Self {
+        TCE::NotId(Box::new(val))
+    }
+
+    pub fn not_universe(val: Val) -> Self {
+        TCE::NotUniverse(Box::new(val))
+    }
+
+    pub fn cons_return(cons: Ident, ret: Option<Term>) -> Self {
+        TCE::ConsReturn(cons, ret.map(Box::new))
+    }
+
+    pub fn not_positive(data: Ident, occ: Term, loc: Loc) -> Self {
+        TCE::NotPositive(data, Box::new(occ), loc)
+    }
+
+    pub fn not_guarded(name: Ident, call: Term, loc: Loc) -> Self {
+        TCE::NotGuarded(name, Box::new(call), loc)
+    }
+
     pub fn not_codata(val: Val) -> Self {
         TCE::NotCodata(Box::new(val))
     }
@@ -81,6 +158,26 @@ impl TCE {
     pub fn split_on_non_var(a: Term, b: Term) -> Self {
         Self::boxing_two(a, b, TCE::SplitOnNonVar)
     }
+
+    pub fn unify_conflict(a: Term, b: Term) -> Self {
+        Self::boxing_two(a, b, TCE::UnifyConflict)
+    }
+
+    pub fn unify_cycle(a: Term, b: Term) -> Self {
+        Self::boxing_two(a, b, TCE::UnifyCycle)
+    }
+
+    pub fn unify_stuck(a: Term, b: Term) -> Self {
+        Self::boxing_two(a, b, TCE::UnifyStuck)
+    }
+
+    pub fn meta_not_pattern(meta: Term, term: Term) -> Self {
+        Self::boxing_two(meta, term, TCE::MetaNotPattern)
+    }
+
+    pub fn meta_scope(mi: MI, sol: Term) -> Self {
+        TCE::MetaScope(mi, Box::new(sol))
+    }
 }
 
 impl Display for TCE {
@@ -96,11 +193,123 @@ impl Display for TCE {
             TCE::NotTerm(proj) => write!(f, "Cannot project `{}` on a datatype.", proj),
             TCE::NotData(val) => write!(f, "`{}` is not a datatype.", val),
             TCE::NotCodata(val) => write!(f, "`{}` is not a record type.", val),
+            TCE::NotId(val) => write!(f, "`{}` is not an identity type.", val),
+            TCE::NotUniverse(val) => write!(f, "`{}` is not a universe.", val),
+            TCE::NotPositive(data, occ, loc) => write!(
+                f,
+                "`{}` is not strictly positive, because it occurs in `{}` (at {}).",
+                data.text, occ, loc
+            ),
+            TCE::NotRecordCons(cons, field) => write!(
+                f,
+                "Cannot define the constructor `{}` (at {}), because the type of `{}` \
+                 mentions `self` other than projecting the fields before it (at {}).",
+                cons.text, cons.loc, field.text, field.loc
+            ),
             TCE::SplitOnNonVar(term, ty) => {
                 write!(f, "Splitting on non variable `{}` (of type `{}`)", term, ty)
             }
+            TCE::NotEmpty(ty, loc) => write!(
+                f,
+                "`{}` is not obviously empty, so it can't be matched with an absurd pattern \
+                 (in the clause at {}).",
+                ty, loc
+            ),
+            TCE::AbsurdBody(loc) => write!(
+                f,
+                "The clause (at {}) has absurd patterns, so it should have no body.",
+                loc
+            ),
+            TCE::MissingBody(loc) => write!(
+                f,
+                "The clause (at {}) has no absurd patterns, so it should have a body.",
+                loc
+            ),
+            TCE::MissingClauses(name, cases) => {
+                write!(f, "Incomplete pattern matching for `{}`. Missing cases:", name.text)?;
+                for case in cases {
+                    write!(f, "\n  {}", name.text)?;
+                    for pat in case {
+                        write!(f, " {}", pat)?;
+                    }
+                }
+                Ok(())
+            }
+            TCE::UnifyConflict(a, b) => write!(
+                f,
+                "`{}` and `{}` are built from different constructors, so they can never be equal.",
+                a, b
+            ),
+            TCE::UnifyCycle(a, b) => write!(
+                f,
+                "`{}` and `{}` can never be equal, because one occurs in the other.",
+                a, b
+            ),
+            TCE::UnifyStuck(a, b) => write!(
+                f,
+                "I'm not sure if there should be a case for the equation `{}` = `{}`.",
+                a, b
+            ),
+            TCE::UnifyReflexive(a, b, ty, loc) => write!(
+                f,
+                "Cannot eliminate the reflexive equation `{}` = `{}` of type `{}` (at {}), \
+                 because K has been disabled.",
+                a, b, ty, loc
+            ),
             TCE::MetaRecursion(mi) => write!(f, "Trying to solve a recursive meta of index {}.", mi),
-            TCE::MetaUnsolved(mi) => write!(f, "Unsolved meta of index {}.", mi),
+            TCE::MetaUnsolved(_, info) => match &info.origin {
+                MetaOrigin::ImplicitArg(Some(param), fun) => write!(
+                    f,
+                    "Cannot infer implicit argument `{}` of `{}` at {}.",
+                    param, fun, info.loc
+                ),
+                MetaOrigin::ImplicitArg(None, fun) => write!(
+                    f,
+                    "Cannot infer an implicit argument of `{}` at {}.",
+                    fun, info.loc
+                ),
+                MetaOrigin::User(name) => {
+                    write!(f, "Cannot infer the meta `?{}` at {}.", name, info.loc)
+                }
+                MetaOrigin::Universe(ty) => write!(
+                    f,
+                    "Cannot infer the universe level of `{}` at {}.",
+                    ty, info.loc
+                ),
+                MetaOrigin::Postponed(expr) => write!(
+                    f,
+                    "Cannot check `{}` at {}, because its type is unknown.",
+                    expr, info.loc
+                ),
+                MetaOrigin::ParamType(Some(param), fun) => write!(
+                    f,
+                    "Cannot infer the type of parameter `{}` of `{}` at {}.",
+                    param, fun, info.loc
+                ),
+                MetaOrigin::ParamType(None, fun) => write!(
+                    f,
+                    "Cannot infer the type of a parameter of `{}` at {}.",
+                    fun, info.loc
+                ),
+            },
+            TCE::MetaNotPattern(meta, term) => write!(
+                f,
+                "Cannot solve `{}` = `{}`, because the meta is not applied to distinct variables.",
+                meta, term
+            ),
+            TCE::MetaScope(mi, sol) => write!(
+                f,
+                "Cannot solve meta of index {} with `{}`, \
+                 which mentions variables out of the scope of the meta.",
+                mi, sol
+            ),
+            TCE::UnsolvedConstraints(constraints) => {
+                f.write_str("Unsolved constraints:")?;
+                for constraint in constraints {
+                    write!(f, "\n  {}", constraint)?;
+                }
+                Ok(())
+            }
             TCE::DifferentLevel(expr, expected_to_be_small, big) => write!(
                 f,
                 "Expression `{}` has level {}, which is not smaller than {}.",
@@ -118,10 +327,58 @@ impl Display for TCE {
                 "`{}` (at {}) and `{}` (at {}) are different (conversion check is not structural in Narc).",
                 a.text, a.loc, b.text, b.loc
             ),
+            TCE::DifferentConsData(cons, data) => write!(
+                f,
+                "Datatype `{}` does not have constructor `{}` (at {}).",
+                data.text, cons.text, cons.loc
+            ),
             TCE::Blocked(b) => b.fmt(f),
             TCE::NoSuchProj(s) => write!(f, "No such field `{}`.", s),
-            // TODO: display
-            TCE::CantElim(e) => write!(f, "Cannot eliminate-using pattern `{:?}`.", e),
+            TCE::CantElim(ty, loc) => write!(
+                f,
+                "Too many patterns for `{}`, which is not a function type \
+                 (in the clause at {}).",
+                ty, loc
+            ),
+            TCE::UnexpectedImplicit(None) => f.write_str("Unexpected implicit argument."),
+            TCE::UnexpectedImplicit(Some(name)) => write!(
+                f,
+                "Unexpected implicit argument for `{}` (at {}), no such implicit parameter here.",
+                name.text, name.loc
+            ),
+            TCE::ConsArity(cons, expected, actual) => write!(
+                f,
+                "Constructor `{}` (at {}) expects {} arguments, but {} were given.",
+                cons.text, cons.loc, expected, actual
+            ),
+            TCE::ConsReturn(cons, Some(ret)) => write!(
+                f,
+                "Constructor `{}` (at {}) should return its datatype applied to the parameters, not `{}`.",
+                cons.text, cons.loc, ret
+            ),
+            TCE::ConsReturn(cons, None) => write!(
+                f,
+                "Constructor `{}` (at {}) needs a return type, because its datatype has indices.",
+                cons.text, cons.loc
+            ),
+            TCE::DataSortIndexed(data) => write!(
+                f,
+                "The universe of `{}` (at {}) cannot depend on its indices.",
+                data.text, data.loc
+            ),
+            TCE::NonTerminating(name, calls) => {
+                write!(f, "Termination checking failed for `{}`. Problematic calls:", name.text)?;
+                for (loc, call) in calls {
+                    write!(f, "\n  `{}` (in the clause at {})", call, loc)?;
+                }
+                Ok(())
+            }
+            TCE::NotGuarded(name, call, loc) => write!(
+                f,
+                "The corecursive call `{}` of `{}` (in the clause at {}) \
+                 is not guarded by a projection copattern.",
+                call, name.text, loc
+            ),
         }
     }
 }
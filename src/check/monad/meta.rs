@@ -3,9 +3,12 @@ use std::{
     rc::Rc,
 };
 
-use voile_util::{meta::MI, uid::DBI};
+use voile_util::{loc::Loc, meta::MI, uid::DBI};
 
-use crate::syntax::core::subst::{RedEx, Subst};
+use crate::syntax::{
+    common::Bind,
+    core::subst::{RedEx, Subst},
+};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum MetaSol<Val> {
@@ -33,12 +36,59 @@ impl<R, T: RedEx<R>> RedEx<MetaSol<R>> for MetaSol<T> {
     }
 }
 
+/// Why is a meta variable created?
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MetaOrigin {
+    /// An implicit argument, with the name of the parameter (if any)
+    /// and the function it's applied to.
+    ImplicitArg(Option<String>, String),
+    /// A user-written meta like `_x`, with its name.
+    User(String),
+    /// The universe level of a type whose type is a meta,
+    /// with the type (like `_x`).
+    Universe(String),
+    /// An expression whose type-checking is postponed, with the expression.
+    Postponed(String),
+    /// The type of a parameter of a function without a signature,
+    /// with the name of the parameter (if any) and the function.
+    ParamType(Option<String>, String),
+}
+
+/// Information of a meta variable, except its solution.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Monad.Base.html#MetaVariable).
+#[derive(Debug, Clone)]
+pub struct MetaInfo<Val> {
+    /// Expected type of the meta, in the context `tele`.
+    pub ty: Val,
+    /// Local typing context where the meta is created.
+    pub tele: Vec<Bind<Val>>,
+    pub loc: Loc,
+    pub origin: MetaOrigin,
+}
+
+impl<Val> MetaInfo<Val> {
+    pub fn new(ty: Val, tele: Vec<Bind<Val>>, loc: Loc, origin: MetaOrigin) -> Self {
+        Self {
+            ty,
+            tele,
+            loc,
+            origin,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
-pub struct MetaContext<Val>(Vec<MetaSol<Val>>);
+pub struct MetaContext<Val> {
+    solutions: Vec<MetaSol<Val>>,
+    infos: Vec<MetaInfo<Val>>,
+}
 
 impl<Val> Default for MetaContext<Val> {
     fn default() -> Self {
-        MetaContext(Vec::new())
+        MetaContext {
+            solutions: Vec::new(),
+            infos: Vec::new(),
+        }
     }
 }
 
@@ -50,7 +100,7 @@ impl<Val> MetaSol<Val> {
 
 impl<Val> MetaContext<Val> {
     pub fn solutions(&self) -> &Vec<MetaSol<Val>> {
-        &self.0
+        &self.solutions
     }
 
     pub fn solution(&self, index: MI) -> &MetaSol<Val> {
@@ -58,21 +108,24 @@ impl<Val> MetaContext<Val> {
     }
 
     pub fn mut_solutions(&mut self) -> &mut Vec<MetaSol<Val>> {
-        &mut self.0
+        &mut self.solutions
+    }
+
+    pub fn info(&self, index: MI) -> &MetaInfo<Val> {
+        &self.infos[index.0]
     }
 
-    /// Add many unsolved metas to the context.
-    pub fn expand_with_fresh_meta(&mut self, meta_count: MI) {
-        debug_assert!(self.solutions().len() <= meta_count.0);
-        self.mut_solutions()
-            .resize_with(meta_count.0, Default::default);
+    /// Are there metas that are not solved yet?
+    pub fn has_unsolved(&self) -> bool {
+        (self.solutions.iter()).any(|sol| matches!(sol, MetaSol::Unsolved))
     }
 
     /// Create a new valid but unsolved meta variable,
     /// used for generating fresh metas during elaboration.
-    pub fn fresh_meta(&mut self, new_meta: impl FnOnce(MI) -> Val) -> Val {
+    pub fn fresh_meta(&mut self, new_meta: impl FnOnce(MI) -> Val, info: MetaInfo<Val>) -> Val {
         let meta = new_meta(MI(self.solutions().len()));
         self.mut_solutions().push(MetaSol::Unsolved);
+        self.infos.push(info);
         meta
     }
 }
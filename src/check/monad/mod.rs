@@ -3,14 +3,20 @@ use crate::syntax::{
     core::{Tele, TermInfo, Val},
 };
 
-pub use self::{error::*, meta::*, state::*};
+pub use self::{constraint::*, error::*, meta::*, state::*, warning::*};
 
+/// Postponed conversion checks, blocked on metas.
+mod constraint;
 /// `Control.Monad.Except`, as type-checking error.
 mod error;
 /// A reworked version of `voile_util::meta`.
 mod meta;
+/// Printing core terms with names, for error messages.
+mod pretty;
 /// `Control.Monad.State`, as type-checking state.
 mod state;
+/// Type-checking warnings, which don't stop the type-checking.
+mod warning;
 
 /// Type-Checking Monad.
 pub type TCM<T = TCS> = Result<T, TCE>;
@@ -34,7 +40,7 @@ impl TCS {
 
     pub fn considerate_of(desugar: &DesugarState) -> Self {
         let mut tcs = TCS::default();
-        // tcs.meta_context.expand_with_fresh_meta(desugar.meta_count);
+        tcs.names = desugar.names.clone();
         tcs.reserve_local_variables(desugar.decls.len());
         tcs
     }
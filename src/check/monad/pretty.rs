@@ -0,0 +1,123 @@
+use std::fmt::{Display, Error, Formatter, Write};
+
+use voile_util::{
+    tags::Plicit,
+    uid::{DBI, UID},
+};
+
+use crate::{
+    check::{monad::TCS, pats::CorePat},
+    syntax::{
+        core::{Closure, Elim, TeleS, Term, Val},
+        pat::Pat,
+    },
+};
+
+impl TCS {
+    /// Print `term` (in the context `tele`) with the user-written names of
+    /// the variables and the definitions, for error messages.
+    pub fn pretty(&self, tele: &TeleS, term: &Term) -> String {
+        let names = (tele.iter().enumerate())
+            .map(|(i, bind)| self.var_name(bind.name, i))
+            .collect();
+        Named(self, names, term).to_string()
+    }
+
+    /// Print the pattern `pat` (in the context `tele`), with the names of the
+    /// variables in its forced terms.
+    pub fn pretty_pat(&self, tele: &TeleS, pat: &CorePat) -> String {
+        match pat {
+            Pat::Cons(_, head, pats) if !pats.is_empty() => {
+                let pats = (pats.iter().map(|pat| self.pretty_pat(tele, pat))).collect::<Vec<_>>();
+                format!("({} {})", head.name.text, pats.join(" "))
+            }
+            Pat::Forced(term) => format!("|_{}_|", self.pretty(tele, term)),
+            pat => pat.to_string(),
+        }
+    }
+
+    fn var_name(&self, uid: UID, depth: usize) -> String {
+        match self.name_of(uid) {
+            Some(name) => name.clone(),
+            None => format!("x{}", depth),
+        }
+    }
+}
+
+/// A term with the names of the variables in its context, the last name is
+/// the variable of DBI `0`.
+struct Named<'a>(&'a TCS, Vec<String>, &'a Term);
+
+impl<'a> Named<'a> {
+    fn under(&self, name: String, term: &'a Term) -> Self {
+        let mut names = self.1.clone();
+        names.push(name);
+        Named(self.0, names, term)
+    }
+
+    fn sub(&self, term: &'a Term) -> Self {
+        Named(self.0, self.1.clone(), term)
+    }
+
+    fn var(&self, DBI(ix): DBI) -> String {
+        match self.1.len().checked_sub(ix + 1) {
+            Some(i) => self.1[i].clone(),
+            None => format!("{}", ix),
+        }
+    }
+
+    fn app(&self, f: &mut Formatter, fun: &impl Display, elims: &[Elim]) -> Result<(), Error> {
+        if elims.is_empty() {
+            return fun.fmt(f);
+        }
+        write!(f, "({}", fun)?;
+        for elim in elims {
+            match elim {
+                Elim::App(a) => write!(f, " {}", self.sub(a))?,
+                Elim::Proj(field) => write!(f, " .{}", field)?,
+            }
+        }
+        f.write_char(')')
+    }
+
+    fn args(&self, f: &mut Formatter, fun: &impl Display, args: &[Term]) -> Result<(), Error> {
+        let elims = args.iter().cloned().map(Elim::app).collect::<Vec<_>>();
+        self.app(f, fun, &elims)
+    }
+}
+
+impl<'a> Display for Named<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        use Val::*;
+        let val = match self.2 {
+            Term::Redex(_, ident, elims) => return self.app(f, &ident.text, elims),
+            Term::Whnf(val) => val,
+        };
+        match val {
+            Var(ix, elims) => self.app(f, &self.var(*ix), elims),
+            Meta(mi, elims) => self.app(f, &format!("?{}", mi), elims),
+            Data(data) => {
+                let name = &self.0.def(data.def).def_name().text;
+                self.args(f, name, &data.args)
+            }
+            Cons(head, args) => self.args(f, &head.name.text, args),
+            Pi(bind, Closure::Plain(body)) => {
+                let name = self.0.var_name(bind.name, self.1.len());
+                let ty = self.sub(&bind.ty);
+                let body = self.under(name.clone(), body);
+                match (bind.licit, self.0.name_of(bind.name)) {
+                    (Plicit::Ex, None) => write!(f, "({} -> {})", ty, body),
+                    (Plicit::Ex, Some(_)) => write!(f, "(({} : {}) -> {})", name, ty, body),
+                    (Plicit::Im, _) => write!(f, "({{{} : {}}} -> {})", name, ty, body),
+                }
+            }
+            Lam(bind, Closure::Plain(body)) => {
+                let name = self.0.var_name(bind.name, self.1.len());
+                let body = self.under(name.clone(), body);
+                write!(f, "(\\{}. {})", name, body)
+            }
+            Id(_, a, b) => write!(f, "({} = {})", self.sub(a), self.sub(b)),
+            e => e.fmt(f),
+        }
+    }
+}
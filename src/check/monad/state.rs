@@ -1,12 +1,24 @@
-use std::fmt::{Display, Error, Formatter, Write};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Error, Formatter, Write},
+};
 
-use voile_util::uid::{DBI, GI, UID};
+use voile_util::{
+    loc::{Ident, Loc},
+    uid::{DBI, GI, UID},
+};
 
 use crate::{
-    check::{monad::meta::MetaContext, rules::ERROR_MSG},
+    check::{
+        monad::{
+            meta::{MetaContext, MetaInfo, MetaOrigin},
+            Constraint, TCE, TCM, TCW,
+        },
+        rules::ERROR_MSG,
+    },
     syntax::core::{
         subst::{DeBruijn, RedEx, Subst},
-        Bind, Decl, Let, LetList, Tele, Term,
+        Bind, Decl, Let, Tele, Term,
     },
 };
 
@@ -21,8 +33,13 @@ pub struct TCS {
     indentation: Indentation,
     /// Where are we?
     current_checking_def: Option<GI>,
+    /// The clause being checked (or the function, when checking
+    /// the coverage), for error messages.
+    pub current_loc: Loc,
     /// Are we tracing the type checking process?
     pub trace_tc: bool,
+    /// Are we rejecting the K axiom (deleting reflexive equations)?
+    pub without_k: bool,
     /// Conversion check depth.
     pub unify_depth: DBI,
 
@@ -30,10 +47,16 @@ pub struct TCS {
     pub sigma: Sigma,
     /// Local typing context.
     pub gamma: Tele,
-    /// Let bindings.
-    pub lets: LetList,
+    /// Let bindings, each with the length of `gamma` when it's introduced.
+    pub lets: Vec<(usize, Let)>,
     /// Meta variable context, scoped. Always global.
     pub meta_ctx: Vec<MetaContext<Term>>,
+    /// Warnings reported so far.
+    pub warnings: Vec<TCW>,
+    /// Postponed constraints, woken up when their blockers are solved.
+    pub constraints: Vec<Constraint>,
+    /// User-written names of the parameters, used in error messages.
+    pub names: BTreeMap<UID, String>,
 }
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -75,6 +98,12 @@ impl TCS {
         self.current_checking_def = None;
     }
 
+    /// Go back to the metas of a definition checked before,
+    /// like the ones in a signature that are solved by the clauses.
+    pub fn reenter_def(&mut self, def: GI) {
+        self.current_checking_def = Some(def);
+    }
+
     pub fn tc_shallower(&mut self) {
         if self.indentation.tc_depth > 0 {
             self.indentation.tc_depth -= 1;
@@ -90,6 +119,18 @@ impl TCS {
         debug_assert_eq!(self.unify_depth, DBI(0));
         debug_assert!(self.lets.is_empty());
         debug_assert!(self.gamma.is_empty());
+        debug_assert!(self.constraints.is_empty());
+    }
+
+    /// Report the constraints that are still postponed,
+    /// should be invoked at the end of a definition.
+    pub fn check_constraints(&mut self) -> TCM<()> {
+        if self.constraints.is_empty() {
+            Ok(())
+        } else {
+            let constraints = std::mem::take(&mut self.constraints);
+            Err(TCE::UnsolvedConstraints(constraints))
+        }
     }
 
     pub fn reserve_local_variables(&mut self, additional: usize) {
@@ -100,8 +141,25 @@ impl TCS {
 
     /// Create a new valid but unsolved meta variable,
     /// used for generating fresh metas during elaboration.
-    pub fn fresh_meta(&mut self) -> Term {
-        self.mut_meta_ctx().fresh_meta(|m| Term::meta(m, vec![]))
+    /// The meta has type `ty` in the current local context.
+    pub fn fresh_meta(&mut self, ty: Term, loc: Loc, origin: MetaOrigin) -> Term {
+        let info = MetaInfo::new(ty, self.gamma.clone(), loc, origin);
+        self.mut_meta_ctx()
+            .fresh_meta(|m| Term::meta(m, vec![]), info)
+    }
+
+    /// The user-written name of a parameter.
+    pub fn name_of(&self, uid: UID) -> Option<&String> {
+        self.names.get(&uid)
+    }
+
+    /// Whether an implicit argument (or pattern) named `name` is for
+    /// the parameter `uid`. Unnamed ones are for any implicit parameter.
+    pub fn is_named(&self, uid: UID, name: &Option<Ident>) -> bool {
+        match name {
+            Some(name) => self.name_of(uid) == Some(&name.text),
+            None => true,
+        }
     }
 
     pub fn def(&self, ix: GI) -> &Decl {
@@ -125,7 +183,14 @@ impl TCS {
     }
 
     pub fn local_by_id_safe(&self, id: UID) -> Option<Let> {
-        let lookup_let = || self.let_by_id_safe(id).cloned();
+        let lookup_let = || {
+            let (len, l) = self.let_by_id_safe(id)?;
+            // The let binding might be introduced in a shorter context.
+            Some(
+                l.clone()
+                    .reduce_dbi(Subst::raise(DBI(self.gamma.len() - len))),
+            )
+        };
         let lookup_gamma = || {
             let (i, ty) = self.gamma_by_id_safe(id)?;
             let ty = ty.clone().reduce_dbi(Subst::raise(i + 1));
@@ -134,8 +199,12 @@ impl TCS {
         lookup_let().or_else(lookup_gamma)
     }
 
-    fn let_by_id_safe(&self, id: UID) -> Option<&Let> {
-        self.lets.iter().find(|b| b.bind.name == id)
+    fn let_by_id_safe(&self, id: UID) -> Option<&(usize, Let)> {
+        self.lets.iter().find(|(_, b)| b.bind.name == id)
+    }
+
+    pub fn push_let(&mut self, l: Let) {
+        self.lets.push((self.gamma.len(), l));
     }
 
     fn gamma_by_id_safe(&self, id: UID) -> Option<(DBI, &Bind)> {
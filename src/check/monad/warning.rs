@@ -0,0 +1,25 @@
+use std::fmt::{Display, Error as FmtError, Formatter};
+
+use voile_util::loc::{Ident, Loc};
+
+/// Type-Checking Warning.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Monad.Base.html#Warning).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum TCW {
+    /// The function and its clauses that are never used by the case tree.
+    UnreachableClauses(Ident, Vec<Loc>),
+}
+
+impl Display for TCW {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
+        match self {
+            TCW::UnreachableClauses(name, locs) => {
+                write!(f, "Unreachable clauses of `{}`:", name.text)?;
+                for loc in locs {
+                    write!(f, "\n  {}", loc)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -1,14 +1,23 @@
-use std::convert::TryFrom;
+use std::{convert::TryFrom, rc::Rc};
 
 use voile_util::uid::DBI;
 
-use crate::syntax::{
-    core::{subst::DeBruijn, Elim, Term},
-    pat::{Copat, Pat},
+use crate::{
+    check::rules::ERROR_MSG,
+    syntax::{
+        core::{
+            subst::{DeBruijn, PrimSubst, RedEx, Subst},
+            Elim, Term,
+        },
+        pat::{Copat, Pat},
+    },
 };
 
 pub type CoreCopat = Copat<DBI, Term>;
 pub type CorePat = Pat<DBI, Term>;
+/// Substitution on patterns, produced by splitting.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.Syntax.Internal.Pattern.html#PatternSubstitution).
+pub type PatSubst = PrimSubst<CorePat>;
 
 impl TryFrom<CoreCopat> for Term {
     type Error = String;
@@ -28,7 +37,7 @@ impl From<CoreCopat> for Elim {
     fn from(p: CoreCopat) -> Elim {
         match p {
             Copat::Proj(field) => Elim::Proj(field),
-            Copat::App(p) => From::from(p),
+            Copat::App(p) | Copat::Implicit(_, p) => From::from(p),
         }
     }
 }
@@ -51,3 +60,57 @@ impl From<CorePat> for Elim {
         }
     }
 }
+
+impl DeBruijn for CorePat {
+    fn dbi_view(&self) -> Option<DBI> {
+        match self {
+            Pat::Var(ix) => Some(*ix),
+            _ => None,
+        }
+    }
+
+    fn from_dbi(dbi: DBI) -> Self {
+        Pat::Var(dbi)
+    }
+}
+
+impl PatSubst {
+    /// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.Syntax.Internal.Pattern.html#patternToTerm).
+    pub fn to_term_subst(&self) -> Rc<Subst> {
+        self.map(&|pat| Term::try_from(pat.clone()).expect(ERROR_MSG))
+    }
+}
+
+impl RedEx<CorePat, CorePat> for CorePat {
+    fn reduce_dbi(self, subst: Rc<PatSubst>) -> CorePat {
+        match self {
+            Pat::Var(ix) => subst.lookup(ix),
+            Pat::Cons(f, c, pats) => Pat::Cons(f, c, pats.reduce_dbi(subst)),
+            Pat::Forced(t) => Pat::Forced(t.reduce_dbi(subst.to_term_subst())),
+            Pat::Refl => Pat::Refl,
+            Pat::Absurd => Pat::Absurd,
+        }
+    }
+}
+
+impl RedEx<CoreCopat, CorePat> for CoreCopat {
+    fn reduce_dbi(self, subst: Rc<PatSubst>) -> CoreCopat {
+        self.map_app(|pat| pat.reduce_dbi(subst))
+    }
+}
+
+impl RedEx<Vec<CorePat>, CorePat> for Vec<CorePat> {
+    fn reduce_dbi(self, subst: Rc<PatSubst>) -> Vec<CorePat> {
+        self.into_iter()
+            .map(|p| p.reduce_dbi(subst.clone()))
+            .collect()
+    }
+}
+
+impl RedEx<Vec<CoreCopat>, CorePat> for Vec<CoreCopat> {
+    fn reduce_dbi(self, subst: Rc<PatSubst>) -> Vec<CoreCopat> {
+        self.into_iter()
+            .map(|p| p.reduce_dbi(subst.clone()))
+            .collect()
+    }
+}
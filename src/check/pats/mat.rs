@@ -4,9 +4,9 @@ use voile_util::uid::DBI;
 
 use crate::{
     check::{
-        monad::TCS,
-        pats::{Blocked, CoreCopat, CorePat, Simpl},
-        rules::ERROR_MSG,
+        monad::{TCE, TCM, TCS},
+        pats::{Blocked, CoreCopat, CorePat, Simpl, Stuck},
+        rules::{simplify_ref, ERROR_MSG},
     },
     syntax::{
         core::{subst::Subst, Elim, Term, Val},
@@ -68,11 +68,11 @@ fn matched<T, H: BuildHasher>(mut map: HashMap<DBI, T, H>, max: usize) -> Vec<T>
 pub fn match_copats(
     tcs: &TCS,
     mut p: impl ExactSizeIterator<Item = (CoreCopat, Elim)>,
-) -> (Match, Vec<Elim>) {
+) -> TCM<(Match, Vec<Elim>)> {
     let mut mat = Match::with_capacity(p.len());
     let mut elims = Vec::with_capacity(p.len());
     while let Some((copat, elim)) = p.next() {
-        let (m, e) = match_copat(tcs, copat, elim);
+        let (m, e) = match_copat(tcs, copat, elim)?;
         match m {
             Match::No if e.is_proj() => {
                 elims.push(e);
@@ -86,13 +86,19 @@ pub fn match_copats(
                 // left-to-right.
                 let copy = p.collect::<Vec<_>>();
                 let mut copied_elims = copy.iter().map(|(_, e)| e).cloned().collect();
-                let (m, _) = match_copats(tcs, copy.into_iter());
-                mat = m;
+                let (m, _) = match_copats(tcs, copy.into_iter())?;
+                // A mismatch is a mismatch, regardless of the rest.
+                mat = match m {
+                    Match::Dunno(d) => Match::Dunno(d),
+                    _ => Match::No,
+                };
+                elims.push(e);
                 elims.append(&mut copied_elims);
                 break;
             }
             Match::Dunno(d) => {
                 mat = Match::Dunno(d);
+                elims.push(e);
                 elims.extend(p.map(|(_, e)| e));
                 break;
             }
@@ -102,36 +108,76 @@ pub fn match_copats(
             }
         }
     }
-    (mat, elims)
+    Ok((mat, elims))
 }
 
-fn match_copat(tcs: &TCS, p: CoreCopat, e: Elim) -> (Match, Elim) {
+fn match_copat(tcs: &TCS, p: CoreCopat, e: Elim) -> TCM<(Match, Elim)> {
     match (p, e) {
-        (Copat::Proj(s0), Elim::Proj(s1)) => {
-            if s0 == s1 {
-                (Match::Yes(Simpl::Yes, Default::default()), Elim::Proj(s1))
-            } else {
-                (Match::No, Elim::Proj(s1))
-            }
+        (Copat::Proj(s0), Elim::Proj(s1)) => Ok(if s0 == s1 {
+            (Match::Yes(Simpl::Yes, Default::default()), Elim::Proj(s1))
+        } else {
+            (Match::No, Elim::Proj(s1))
+        }),
+        (Copat::Proj(..), Elim::App(a)) => Ok((Match::No, Elim::App(a))),
+        (Copat::App(..), Elim::Proj(s)) | (Copat::Implicit(..), Elim::Proj(s)) => {
+            Ok((Match::No, Elim::Proj(s)))
         }
-        (Copat::Proj(..), Elim::App(a)) => (Match::No, Elim::App(a)),
-        (Copat::App(..), Elim::Proj(s)) => (Match::No, Elim::Proj(s)),
-        (Copat::App(p), Elim::App(t)) => {
-            let (m, t) = match_pat(tcs, p, *t);
-            (m, Elim::app(t))
+        (Copat::App(p), Elim::App(t)) | (Copat::Implicit(_, p), Elim::App(t)) => {
+            let (m, t) = match_pat(tcs, p, *t)?;
+            Ok((m, Elim::app(t)))
         }
     }
 }
 
-fn match_pat(tcs: &TCS, p: CorePat, t: Term) -> (Match, Term) {
-    match (p, t) {
-        (Pat::Var(i), t) => (Match::Yes(Simpl::No, once((i, t.clone())).collect()), t),
-        (Pat::Forced(_), t) => (Match::Yes(Simpl::No, Default::default()), t),
-        (Pat::Absurd, _) => unreachable!(),
-        (Pat::Refl, Term::Whnf(Val::Refl)) => (
-            Match::Yes(Simpl::Yes, Default::default()),
-            Term::Whnf(Val::Refl),
-        ),
-        _ => unimplemented!(),
+fn match_pats(tcs: &TCS, pats: Vec<CorePat>, terms: Vec<Term>) -> TCM<(Match, Vec<Term>)> {
+    debug_assert_eq!(pats.len(), terms.len());
+    let elims = terms.into_iter().map(Elim::app);
+    let copats = pats.into_iter().map(Copat::App).zip(elims);
+    let (m, elims) = match_copats(tcs, copats.collect::<Vec<_>>().into_iter())?;
+    let terms = elims.into_iter().map(Elim::into_app).collect();
+    Ok((m, terms))
+}
+
+fn match_pat(tcs: &TCS, p: CorePat, t: Term) -> TCM<(Match, Term)> {
+    match p {
+        Pat::Var(i) => Ok((Match::Yes(Simpl::No, once((i, t.clone())).collect()), t)),
+        Pat::Forced(_) => Ok((Match::Yes(Simpl::No, Default::default()), t)),
+        Pat::Absurd => unreachable!(),
+        Pat::Refl => match simplify_ref(tcs, t) {
+            Ok(Val::Refl) => Ok((
+                Match::Yes(Simpl::Yes, Default::default()),
+                Term::reflexivity(),
+            )),
+            Ok(val) => Ok(stuck_on(val)),
+            Err(TCE::Blocked(b)) => Ok(blocked(*b)),
+            Err(e) => Err(e),
+        },
+        Pat::Cons(_, head, pats) => match simplify_ref(tcs, t) {
+            Ok(Val::Cons(con, args)) => {
+                if con.cons_ix != head.cons_ix {
+                    return Ok((Match::No, Term::cons(con, args)));
+                }
+                let (m, args) = match_pats(tcs, pats, args)?;
+                Ok((m, Term::cons(con, args)))
+            }
+            Ok(val) => Ok(stuck_on(val)),
+            Err(TCE::Blocked(b)) => Ok(blocked(*b)),
+            Err(e) => Err(e),
+        },
     }
 }
+
+/// The term is in whnf but is not the one we're matching against,
+/// like a variable or a meta.
+fn stuck_on(val: Val) -> (Match, Term) {
+    let stuck = match &val {
+        Val::Meta(mi, ..) => Stuck::OnMeta(*mi),
+        _ => Stuck::OnElim(Elim::app(Term::Whnf(val.clone()))),
+    };
+    (Match::Dunno(Blocked::new(stuck, ())), Term::Whnf(val))
+}
+
+fn blocked(b: Blocked<Term>) -> (Match, Term) {
+    let Blocked { stuck, anyway } = b;
+    (Match::Dunno(Blocked::new(stuck, ())), anyway)
+}
@@ -2,12 +2,13 @@ use voile_util::uid::{DBI, GI};
 
 use crate::{
     check::{
-        monad::TCS,
-        rules::{check_decls, simplify},
+        monad::{TCE, TCS},
+        pats::Stuck,
+        rules::{check_decls, simplify, simplify_ref},
     },
     syntax::{
         abs::desugar::desugar_main,
-        core::{subst::DeBruijn, Decl},
+        core::{subst::DeBruijn, Decl, Elim, Term, Val},
         surf::parse_str,
     },
 };
@@ -31,3 +32,176 @@ fn simple_simplify() {
     let (body, _tcs) = simplify(tcs, body).unwrap();
     assert_eq!(body, DeBruijn::from_dbi(DBI(0)))
 }
+
+#[test]
+fn lambda_beta_reduction() {
+    let code = "\
+    data Nat { constructor Zero; };
+
+    definition apply : {A B : Type} -> (A -> B) -> A -> B;
+    clause apply f a = f a;
+
+    definition zero : Nat;
+    clause zero = apply (\\x. x) Zero;
+    ";
+    let desugar = desugar_main(parse_str(code).unwrap()).unwrap();
+    let mut tcs = check_decls(TCS::considerate_of(&desugar), desugar.decls).unwrap();
+    let zero_def = match tcs.take_sigma(GI(4)) {
+        Decl::Func(f) => f,
+        _ => panic!(),
+    };
+    let body = zero_def.clauses[0].body.clone().unwrap();
+    let (body, _tcs) = simplify(tcs, body).unwrap();
+    match body {
+        Val::Cons(head, args) => {
+            assert_eq!(head.name.text, "Zero");
+            assert!(args.is_empty());
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn split_con_simplify() {
+    let code = "\
+    data Nat { constructor Zero; constructor Succ Nat; };
+
+    definition plus : Nat -> Nat -> Nat;
+    clause plus Zero m = m;
+    clause plus (Succ n) m = Succ (plus n m);
+
+    definition two : Nat;
+    clause two = plus (Succ Zero) (Succ Zero);
+    ";
+    let desugar = desugar_main(parse_str(code).unwrap()).unwrap();
+    let mut tcs = check_decls(TCS::considerate_of(&desugar), desugar.decls).unwrap();
+    let two_def = match tcs.take_sigma(GI(6)) {
+        Decl::Func(f) => f,
+        _ => panic!(),
+    };
+    let mut term = two_def.clauses[0].body.clone().unwrap();
+    let mut succ_count = 0;
+    loop {
+        let (val, new_tcs) = simplify(tcs, term).unwrap();
+        tcs = new_tcs;
+        match val {
+            Val::Cons(head, mut args) if head.name.text == "Succ" => {
+                succ_count += 1;
+                term = args.remove(0);
+            }
+            Val::Cons(head, args) => {
+                assert_eq!(head.name.text, "Zero");
+                assert!(args.is_empty());
+                break;
+            }
+            _ => panic!(),
+        }
+    }
+    assert_eq!(succ_count, 2);
+}
+
+#[test]
+fn partial_cons_simplify() {
+    let code = "\
+    data Nat { constructor Zero; };
+    data List (A : Type) { constructor nil; constructor cons (x : A) (xs : List A); };
+    ";
+    let desugar = desugar_main(parse_str(code).unwrap()).unwrap();
+    let tcs = check_decls(TCS::considerate_of(&desugar), desugar.decls).unwrap();
+    let def = |ix| Term::def(GI(ix), tcs.def(GI(ix)).def_name().clone(), vec![]);
+    // `cons` is still waiting for the parameter `A`.
+    let cons = simplify_ref(&tcs, def(4)).unwrap();
+    match &cons {
+        Val::Lam(bind, _) => assert!(bind.is_implicit()),
+        _ => panic!(),
+    }
+    let term = Term::Whnf(cons).apply(vec![def(0), def(1), def(3).apply(vec![def(0)])]);
+    match simplify_ref(&tcs, term).unwrap() {
+        Val::Cons(head, args) => {
+            assert_eq!(head.name.text, "cons");
+            assert_eq!(args.len(), 2);
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn case_tree_simplify() {
+    let code = "\
+    data Nat { constructor Zero; constructor Succ Nat; };
+
+    definition pred : Nat -> Nat -> Nat;
+    clause pred m Zero = m;
+    clause pred m (Succ n) = n;
+
+    definition one : Nat;
+    clause one = pred Zero (Succ (Succ Zero));
+
+    definition stuck : Nat -> Nat;
+    clause stuck n = pred Zero n;
+    ";
+    let desugar = desugar_main(parse_str(code).unwrap()).unwrap();
+    let mut tcs = check_decls(TCS::considerate_of(&desugar), desugar.decls).unwrap();
+    let body = |tcs: &mut TCS, ix| match tcs.take_sigma(ix) {
+        Decl::Func(f) => {
+            assert!(f.case_tree.is_some());
+            f.clauses[0].body.clone().unwrap()
+        }
+        _ => panic!(),
+    };
+    let one = body(&mut tcs, GI(6));
+    match simplify_ref(&tcs, one).unwrap() {
+        Val::Cons(head, args) => {
+            assert_eq!(head.name.text, "Succ");
+            assert_eq!(args.len(), 1);
+        }
+        _ => panic!(),
+    }
+    // Blocked on the second argument, which is the only one split.
+    let stuck = body(&mut tcs, GI(8));
+    match simplify_ref(&tcs, stuck) {
+        Err(TCE::Blocked(b)) => match b.stuck {
+            Stuck::OnElim(Elim::App(t)) => assert_eq!(*t, DeBruijn::from_dbi(DBI(0))),
+            _ => panic!(),
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn projection_simplify() {
+    let code = "\
+    data Nat { constructor Zero; constructor Succ Nat; };
+
+    codata NatStream {
+      projection head : Nat;
+      projection tail : NatStream;
+    };
+
+    definition nats : Nat -> NatStream;
+    clause nats n .head = n;
+    clause nats n .tail = nats (Succ n);
+    ";
+    let desugar = desugar_main(parse_str(code).unwrap()).unwrap();
+    let tcs = check_decls(TCS::considerate_of(&desugar), desugar.decls).unwrap();
+    let name = |ix| tcs.def(GI(ix)).def_name().clone();
+    let zero = Term::simple_def(GI(1), name(1));
+    let nats = Term::def(GI(6), name(6), vec![Elim::app(zero)]);
+    // Prefix: head (nats Zero)
+    let head = Term::def(GI(4), name(4), vec![Elim::app(nats.clone())]);
+    match simplify_ref(&tcs, head).unwrap() {
+        Val::Cons(head, _) => assert_eq!(head.name.text, "Zero"),
+        _ => panic!(),
+    }
+    // Postfix: nats Zero .tail .head
+    let proj = |s: &str| Elim::Proj(s.to_owned());
+    let term = nats.apply_elim(vec![proj("tail"), proj("head")]);
+    match simplify_ref(&tcs, term).unwrap() {
+        Val::Cons(head, _) => assert_eq!(head.name.text, "Succ"),
+        _ => panic!(),
+    }
+    // Stuck: head x
+    let head = Term::def(GI(4), name(4), vec![Elim::from_dbi(DBI(0))]);
+    let var = Val::Var(DBI(0), vec![proj("head")]);
+    assert_eq!(simplify_ref(&tcs, head).unwrap(), var);
+}
@@ -0,0 +1,292 @@
+use std::{collections::BTreeSet, convert::TryFrom};
+
+use voile_util::{
+    loc::ToLoc,
+    uid::{DBI, GI},
+};
+
+use crate::{
+    check::{
+        monad::{TCE, TCM, TCMS, TCS, TCW},
+        pats::CorePat,
+        rules::{
+            clause::{
+                empty::check_empty,
+                split::{
+                    cons_tele, data_args, index_eqs, split_tele, split_tele_con, split_tele_refl,
+                },
+                unify::UnifyEq,
+            },
+            simplify_ref, ERROR_MSG,
+        },
+    },
+    syntax::{
+        core::{
+            subst::{DeBruijn, RedEx, Subst},
+            Bind, CaseTree, Clause, Closure, Decl, Tele, Term, Val,
+        },
+        pat::{Copat, Pat},
+    },
+};
+
+/// Result of matching a clause against the patterns of a case.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Coverage.Match.html#Match).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Matching {
+    Yes,
+    No,
+    /// The clause needs a split on this variable.
+    Block(DBI),
+}
+
+impl Matching {
+    /// `No` is dominant, then `Block`.
+    fn and(self, other: impl FnOnce() -> Self) -> Self {
+        match self {
+            Matching::No => Matching::No,
+            Matching::Yes => other(),
+            Matching::Block(x) => match other() {
+                Matching::No => Matching::No,
+                _ => Matching::Block(x),
+            },
+        }
+    }
+}
+
+/// Match a clause pattern against a (more general) pattern of a case,
+/// collecting the terms for the clause pattern variables in `binds`.
+fn match_pat(clause: &CorePat, case: &CorePat, binds: &mut Vec<(DBI, Term)>) -> Matching {
+    match (clause, case) {
+        (Pat::Var(i), p) => {
+            binds.push((*i, Term::try_from(p.clone()).expect(ERROR_MSG)));
+            Matching::Yes
+        }
+        (Pat::Forced(..), _) => Matching::Yes,
+        (Pat::Refl, Pat::Refl) | (Pat::Refl, Pat::Forced(..)) => Matching::Yes,
+        (Pat::Refl, Pat::Var(x)) | (Pat::Cons(..), Pat::Var(x)) => Matching::Block(*x),
+        (Pat::Cons(_, c, ps), Pat::Cons(_, d, qs)) if c.cons_ix == d.cons_ix => {
+            match_pats(ps, qs, binds)
+        }
+        // Solved by unification.
+        (Pat::Cons(_, c, ps), Pat::Forced(Term::Whnf(Val::Cons(d, args))))
+            if c.cons_ix == d.cons_ix =>
+        {
+            let qs = args.iter().cloned().map(Pat::Forced).collect::<Vec<_>>();
+            match_pats(ps, &qs, binds)
+        }
+        _ => Matching::No,
+    }
+}
+
+fn match_pats(clause: &[CorePat], case: &[CorePat], binds: &mut Vec<(DBI, Term)>) -> Matching {
+    (clause.iter().zip(case)).fold(Matching::Yes, |m, (p, q)| m.and(|| match_pat(p, q, binds)))
+}
+
+/// The application patterns of a clause.
+pub(super) fn app_pats(clause: &Clause) -> Vec<CorePat> {
+    (clause.patterns.iter())
+        .take_while(|copat| !copat.is_proj())
+        .filter_map(|copat| match copat {
+            Copat::App(pat) | Copat::Implicit(_, pat) => Some(pat.clone()),
+            Copat::Proj(..) => None,
+        })
+        .collect()
+}
+
+/// Variables in the argument stack of `pats`, `None` for the forced ones.
+fn stack_vars(pats: &[CorePat], vars: &mut Vec<Option<DBI>>) {
+    for pat in pats {
+        match pat {
+            Pat::Var(i) => vars.push(Some(*i)),
+            Pat::Forced(..) => vars.push(None),
+            Pat::Cons(_, _, pats) => stack_vars(pats, vars),
+            Pat::Refl | Pat::Absurd => {}
+        }
+    }
+}
+
+/// The case tree leaf, where the clause `clause` matches `pats` with `binds`.
+fn done(clause: &Clause, binds: Vec<(DBI, Term)>, tele: &Tele, pats: &[CorePat]) -> CaseTree {
+    let body = match &clause.body {
+        Some(body) => body.clone(),
+        None => return CaseTree::Fail,
+    };
+    let mut terms = vec![None; clause.pat_tele.len()];
+    for (DBI(i), term) in binds {
+        terms[i] = Some(term);
+    }
+    let terms = (terms.into_iter()).map(|term| term.expect(ERROR_MSG));
+    // The arguments that the clause doesn't match on.
+    let rest = (pats.iter().skip(app_pats(clause).len()).cloned())
+        .map(|pat| Term::try_from(pat).expect(ERROR_MSG))
+        .collect();
+    let body = body.reduce_dbi(Subst::parallel(terms)).apply(rest);
+    let mut vars = Vec::with_capacity(pats.len());
+    stack_vars(pats, &mut vars);
+    let stack_len = vars.len();
+    let mut ren = vec![None; tele.len()];
+    for (pos, var) in vars.into_iter().enumerate() {
+        if let Some(DBI(i)) = var {
+            ren[i] = Some(Term::from_dbi(DBI(stack_len - pos - 1)));
+        }
+    }
+    let ren = (ren.into_iter()).map(|term| term.expect(ERROR_MSG));
+    CaseTree::Done(stack_len, body.reduce_dbi(Subst::parallel(ren)))
+}
+
+/// Things collected while building the case tree.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Coverage.html#CoverResult).
+#[derive(Debug, Default)]
+struct Cover {
+    /// Indices of the clauses used in the case tree.
+    used: BTreeSet<usize>,
+    /// The cases not covered by any clause, printed.
+    missing: Vec<Vec<String>>,
+}
+
+/// Cover the case $\Gamma \vdash \overline{ps}$ by the first clause
+/// matching it, splitting on the variables it's blocked on.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Coverage.html#cover).
+fn cover(
+    tcs: &TCS,
+    clauses: &[Clause],
+    tele: Tele,
+    pats: Vec<CorePat>,
+    cover_res: &mut Cover,
+) -> TCM<CaseTree> {
+    for (i, clause) in clauses.iter().enumerate() {
+        let mut binds = Vec::with_capacity(tele.len());
+        match match_pats(&app_pats(clause), &pats, &mut binds) {
+            Matching::No => continue,
+            Matching::Yes => {
+                cover_res.used.insert(i);
+                return Ok(done(clause, binds, &tele, &pats));
+            }
+            Matching::Block(x) => return split(tcs, clauses, tele, pats, x, cover_res),
+        }
+    }
+    // No clause is needed if this case is impossible.
+    let is_empty = |(pos, bind): (usize, &Bind)| {
+        check_empty(tcs, tele[..pos].to_vec(), bind.ty.clone()).is_ok()
+    };
+    if !tele.iter().enumerate().any(is_empty) {
+        let pats = pats.iter().map(|pat| tcs.pretty_pat(&tele, pat));
+        cover_res.missing.push(pats.collect());
+    }
+    Ok(CaseTree::Fail)
+}
+
+/// Split the variable `x` in the case $\Gamma \vdash \overline{ps}$.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Coverage.html#split).
+fn split(
+    tcs: &TCS,
+    clauses: &[Clause],
+    tele: Tele,
+    pats: Vec<CorePat>,
+    x: DBI,
+    cover_res: &mut Cover,
+) -> TCM<CaseTree> {
+    let mut vars = Vec::with_capacity(pats.len());
+    stack_vars(&pats, &mut vars);
+    let pos = (vars.iter().position(|var| *var == Some(x))).expect(ERROR_MSG);
+    let (delta1, dom, delta2) = split_tele(tele, x);
+    match simplify_ref(tcs, dom.ty)? {
+        Val::Data(data) => {
+            let conses = match tcs.def(data.def) {
+                Decl::Data(info) => &info.conses,
+                _ => return Err(TCE::not_data(Val::Data(data))),
+            };
+            let mut branches = Vec::with_capacity(conses.len());
+            for &cons_ix in conses {
+                let cons = match tcs.def(cons_ix) {
+                    Decl::Cons(cons) => cons,
+                    _ => unreachable!(),
+                };
+                let (params, indices) = data_args(tcs, data.def, data.args.clone());
+                let eqs = index_eqs(tcs, cons, &params, indices);
+                let theta = cons_tele(&cons.params, params);
+                let head = cons.head(cons_ix);
+                let (d1, d2) = (delta1.clone(), delta2.clone());
+                let tree = match split_tele_con(tcs, d1, theta, eqs, head, false, d2)? {
+                    Ok((tele, rho, _)) => {
+                        let pats = pats.clone().reduce_dbi(rho);
+                        cover(tcs, clauses, tele, pats, cover_res)?
+                    }
+                    // The indices can't match.
+                    Err(..) => CaseTree::Fail,
+                };
+                branches.push((cons_ix, tree));
+            }
+            Ok(CaseTree::Case(pos, branches))
+        }
+        Val::Id(ty, a, b) => {
+            let eq = UnifyEq::new(*a, *b, *ty);
+            match split_tele_refl(tcs, delta1, eq, delta2)? {
+                Ok((tele, rho)) => {
+                    let pats = pats.reduce_dbi(rho);
+                    let tree = cover(tcs, clauses, tele, pats, cover_res)?;
+                    Ok(CaseTree::Refl(pos, Box::new(tree)))
+                }
+                Err(..) => Ok(CaseTree::Fail),
+            }
+        }
+        e => Err(TCE::not_data(e)),
+    }
+}
+
+/// The first `arity` parameters of the function type `ty`, evaluating the
+/// types of the results, like `progress_lhs_state` does.
+fn params_tele(tcs: &TCS, ty: Term, arity: usize) -> TCM<Tele> {
+    let mut tele = Tele::with_capacity(arity);
+    let mut ty = ty;
+    while tele.len() < arity {
+        match simplify_ref(tcs, ty)? {
+            Val::Pi(bind, Closure::Plain(ret)) => {
+                tele.push(bind.unboxed());
+                ty = *ret;
+            }
+            e => return Err(TCE::NotPi(Term::Whnf(e), tcs.current_loc)),
+        }
+    }
+    Ok(tele)
+}
+
+/// Check if the clauses of the function `def` cover all the cases,
+/// and build the case tree.
+/// Clauses that are not used by the case tree are reported as warnings.
+///
+/// Definitions by copatterns are not covered (we don't split on the result),
+/// they're reduced clause by clause. Hence missing projection clauses are not
+/// reported, the projections without clauses are just stuck.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Coverage.html#coverageCheck).
+pub fn coverage(mut tcs: TCS, def: GI) -> TCMS<Option<CaseTree>> {
+    tcs.current_loc = tcs.def(def).loc();
+    let func = match tcs.def(def) {
+        Decl::Func(func) => func,
+        _ => unreachable!(),
+    };
+    let copats = (func.clauses.iter()).any(|c| c.patterns.iter().any(Copat::is_proj));
+    if copats {
+        return Ok((None, tcs));
+    }
+    let arity = (func.clauses.iter().map(|c| app_pats(c).len()))
+        .max()
+        .unwrap_or_default();
+    let tele = params_tele(&tcs, func.signature.clone(), arity)?;
+    let pats = (0..arity).rev().map(DBI).map(Pat::Var).collect();
+    let mut cover_res = Cover::default();
+    let tree = cover(&tcs, &func.clauses, tele, pats, &mut cover_res)?;
+    if !cover_res.missing.is_empty() {
+        return Err(TCE::MissingClauses(func.name.clone(), cover_res.missing));
+    }
+    let unreachable = (func.clauses.iter().enumerate())
+        .filter(|(i, _)| !cover_res.used.contains(i))
+        .map(|(_, clause)| clause.source)
+        .collect::<Vec<_>>();
+    if !unreachable.is_empty() {
+        let name = func.name.clone();
+        tcs.warnings
+            .push(TCW::UnreachableClauses(name, unreachable));
+    }
+    Ok((Some(tree), tcs))
+}
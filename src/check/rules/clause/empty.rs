@@ -0,0 +1,72 @@
+use voile_util::uid::GI;
+
+use crate::{
+    check::{
+        monad::{TCE, TCM, TCS},
+        rules::{
+            clause::{
+                split::{cons_tele, data_args, index_eqs},
+                unify::{unify, Unification, UnifyEq},
+            },
+            simplify_ref,
+        },
+    },
+    syntax::core::{Decl, Tele, TeleS, Term, Val},
+};
+
+/// Make sure `ty` (in the context `tele`) has no constructors the unifier
+/// can match, which is required by absurd patterns.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Empty.html#isEmptyType).
+pub(super) fn check_empty(tcs: &TCS, tele: Tele, ty: Term) -> TCM<()> {
+    let not_empty =
+        |tele: &TeleS, ty: Val| TCE::NotEmpty(tcs.pretty(tele, &Term::Whnf(ty)), tcs.current_loc);
+    match simplify_ref(tcs, ty)? {
+        Val::Data(data) => match tcs.def(data.def) {
+            // Every constructor fails to match the indices.
+            Decl::Data(info) => {
+                for &cons_ix in &info.conses {
+                    if !cons_is_empty(tcs, &tele, cons_ix, data.args.clone())? {
+                        return Err(not_empty(&tele, Val::Data(data)));
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(not_empty(&tele, Val::Data(data))),
+        },
+        Val::Id(ty, a, b) => match unify(
+            tcs,
+            tele.clone(),
+            vec![UnifyEq::new(*a.clone(), *b.clone(), *ty.clone())],
+        ) {
+            Ok(Unification::No(..)) => Ok(()),
+            Ok(Unification::Yes(..)) | Err(TCE::UnifyStuck(..)) | Err(TCE::UnifyReflexive(..)) => {
+                Err(not_empty(&tele, Val::Id(ty, a, b)))
+            }
+            Err(e) => Err(e),
+        },
+        e => Err(not_empty(&tele, e)),
+    }
+}
+
+/// Whether the indices the constructor `cons_ix` returns can never
+/// be unified with the indices in `args`.
+fn cons_is_empty(tcs: &TCS, tele: &TeleS, cons_ix: GI, args: Vec<Term>) -> TCM<bool> {
+    let cons = match tcs.def(cons_ix) {
+        Decl::Cons(cons) => cons,
+        _ => unreachable!(),
+    };
+    let (params, indices) = data_args(tcs, cons.data, args);
+    let eqs = index_eqs(tcs, cons, &params, indices);
+    if eqs.is_empty() {
+        return Ok(false);
+    }
+    let mut tele = tele.to_vec();
+    tele.extend(cons_tele(&cons.params, params));
+    match unify(tcs, tele, eqs) {
+        Ok(Unification::No(..)) => Ok(true),
+        Ok(Unification::Yes(..)) | Err(TCE::UnifyStuck(..)) | Err(TCE::UnifyReflexive(..)) => {
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
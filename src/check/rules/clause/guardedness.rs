@@ -0,0 +1,54 @@
+use voile_util::uid::GI;
+
+use crate::{
+    check::{
+        monad::{TCE, TCM, TCS},
+        rules::clause::termination::collect_calls,
+    },
+    syntax::core::{Clause, Decl, Elim, Term},
+};
+
+/// Number of projections applied to a call.
+pub(super) fn projections(elims: &[Elim]) -> usize {
+    elims.iter().filter(|elim| elim.is_proj()).count()
+}
+
+/// Number of projection copatterns of a clause.
+pub(super) fn guards(clause: &Clause) -> usize {
+    (clause.patterns.iter())
+        .filter(|copat| copat.is_proj())
+        .count()
+}
+
+/// Check if the corecursive calls of the function `def` are guarded by the
+/// projection copatterns of their clauses, that is, the calls are observed
+/// by fewer projections than the clause.
+/// Clauses without projection copatterns are left to the termination checker.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.Termination.TermCheck.html#termDef).
+pub fn guardedness(tcs: &TCS, def: GI) -> TCM<()> {
+    let func = match tcs.def(def) {
+        Decl::Func(func) => func,
+        _ => unreachable!(),
+    };
+    for clause in &func.clauses {
+        let body = match &clause.body {
+            Some(body) => body,
+            None => continue,
+        };
+        let guards = guards(clause);
+        if guards == 0 {
+            continue;
+        }
+        let mut unguarded = None;
+        collect_calls(tcs, body, 0, &mut |callee, term, _| match term {
+            Term::Redex(_, _, elims) if callee == def && projections(elims) >= guards => {
+                unguarded.get_or_insert_with(|| term.clone());
+            }
+            _ => {}
+        });
+        if let Some(call) = unguarded {
+            return Err(TCE::not_guarded(func.name.clone(), call, clause.source));
+        }
+    }
+    Ok(())
+}
@@ -7,8 +7,9 @@ use crate::{
         pats::CoreCopat,
         rules::{
             clause::{
+                empty::check_empty,
                 eqs::{classify_eqs, AsBind, PatVars},
-                split::{split_con, split_proj},
+                split::{split_con, split_proj, split_refl},
                 state::LhsState,
             },
             term::is_eta_var_ref,
@@ -137,6 +138,11 @@ fn final_check(tcs: TCS, mut lhs: LhsState) -> TCMS<Lhs> {
     // let param_sub = Subst::compose(Subst::compose(pat_sub.clone(), weak_sub),
     // with_sub); TODO: check linearity
     let equations = lhs.problem.equations;
+    for eq in &equations {
+        if let Copat::App(Pat::Absurd) = eq.in_pat {
+            check_empty(&tcs, lhs.tele.clone(), eq.ty.clone())?;
+        }
+    }
     let (classified, tcs) = tcs.under(&mut lhs.tele, |tcs| classify_eqs(tcs, equations))?;
     debug_assert!(classified.other_pats.is_empty());
     let (vars, mut asb) = user_variable_names(&lhs.tele, classified.pat_vars);
@@ -163,35 +169,34 @@ fn final_check(tcs: TCS, mut lhs: LhsState) -> TCMS<Lhs> {
 
 /// Checking a pattern matching lhs recursively.
 /// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.html).
-pub(super) fn check_lhs(mut tcs: TCS, mut lhs: LhsState) -> TCMS<Lhs> {
-    let problem_equations = (lhs.problem.equations.iter())
-        .filter(|e| e.in_pat.is_split())
-        .cloned()
-        .collect::<Vec<_>>();
-    for split in problem_equations {
-        use Copat::{App, Proj};
+pub(super) fn check_lhs(tcs: TCS, mut lhs: LhsState) -> TCMS<Lhs> {
+    let split_ix = (lhs.problem.equations.iter()).position(|e| e.in_pat.is_split());
+    if let Some(eq_ix) = split_ix {
+        use Copat::{App, Implicit, Proj};
         use Pat::{Absurd, Forced};
-        if lhs.problem.is_all_solved() {
-            return final_check(tcs, lhs);
-        }
-        let (is_eta, tcs0) = is_eta_var_ref(tcs, &split.inst, &split.ty)?;
-        tcs = tcs0;
+        let split = lhs.problem.equations.remove(eq_ix);
+        let (is_eta, tcs) = is_eta_var_ref(tcs, &split.inst, &split.ty)?;
         let e = || TCE::split_on_non_var(split.inst.clone(), split.ty.clone());
         let ix = is_eta.ok_or_else(e)?;
-        match split.in_pat {
-            App(Pat::Refl) => unimplemented!(),
-            App(Pat::Cons(force, a, b)) => {
-                let (lhs0, tcs0) = split_con(tcs, ix, lhs, force, a, b)?;
-                tcs = tcs0;
-                lhs = lhs0;
+        let (lhs, tcs) = match split.in_pat {
+            App(Pat::Refl) => split_refl(tcs, ix, lhs)?,
+            App(Pat::Cons(force, head, pats)) => split_con(tcs, ix, eq_ix, lhs, force, head, pats)?,
+            App(Pat::Var(..)) | App(Absurd) | App(Forced(..)) | Implicit(..) | Proj(..) => {
+                unreachable!()
             }
-            App(Pat::Var(..)) | App(Absurd) | App(Forced(..)) | Proj(..) => unreachable!(),
-        }
+        };
+        // Recursively invoke
+        return check_lhs(tcs, lhs);
     }
     if let Some(copat) = lhs.problem.take_first_todo_pat() {
         let proj = match copat {
             Copat::Proj(proj) => proj,
-            e => return Err(TCE::CantElim(e)),
+            _ => {
+                return Err(TCE::CantElim(
+                    tcs.pretty(&lhs.tele, &lhs.target),
+                    tcs.current_loc,
+                ))
+            }
         };
         let (lhs, tcs) = split_proj(tcs, lhs, proj)?;
         // Recursively invoke
@@ -1,13 +1,13 @@
 use crate::{
     check::{
-        monad::{TCMS, TCS},
+        monad::{TCE, TCMS, TCS},
         rules::{
             clause::{
                 eqs::AsBind,
                 lhs::check_lhs,
                 state::{progress_lhs_state, LhsState},
             },
-            term::{check, simplify, HasMeta},
+            term::{check_term, solve_level_bounds, HasMeta},
         },
     },
     syntax::{
@@ -16,10 +16,24 @@ use crate::{
     },
 };
 
+mod coverage;
+mod empty;
 mod eqs;
+mod guardedness;
 mod lhs;
 mod split;
 mod state;
+mod termination;
+mod unify;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::{
+    coverage::coverage,
+    guardedness::guardedness,
+    termination::{mutual_group, termination},
+};
 
 /// Bind as patterns
 fn bind_as_and_tele<T>(
@@ -32,10 +46,10 @@ fn bind_as_and_tele<T>(
     if tcs.lets.len() < as_binds.len() {
         tcs.lets.reserve(as_binds.len() - tcs.lets.len());
     }
+    swap(&mut tcs.gamma, &mut tele);
     for bind in as_binds {
-        tcs.lets.push(bind.into());
+        tcs.push_let(bind.into());
     }
-    swap(&mut tcs.gamma, &mut tele);
     let (thing, mut tcs) = f(tcs)?;
     tcs.lets.clear();
     swap(&mut tcs.gamma, &mut tele);
@@ -65,26 +79,34 @@ pub fn clause(mut tcs: TCS, cls: AbsClause, against: Term) -> TCMS<Clause> {
     Ok((clause, tcs))
 }
 
-fn clause_impl(tcs: TCS, cls: AbsClause, against: Term) -> TCMS<Clause> {
+fn clause_impl(mut tcs: TCS, cls: AbsClause, against: Term) -> TCMS<Clause> {
     let body = cls.body;
+    let source = cls.source;
+    tcs.current_loc = source;
     // Expand pattern synonyms here once we support it.
-    let lhs_state = progress_lhs_state(LhsState::new(cls.patterns, against))?;
+    let def = Term::simple_def(cls.definition, cls.name);
+    let (lhs_state, tcs) = progress_lhs_state(tcs, LhsState::new(cls.patterns, against, def))?;
     let (lhs, tcs) = check_lhs(tcs, lhs_state)?;
     let pat_tele = lhs.tele;
     let ty = lhs.ty;
     let patterns = lhs.pats;
     let has_absurd = lhs.has_absurd;
     bind_as_and_tele(tcs, lhs.as_binds, pat_tele.clone(), |mut tcs| {
-        let body = if has_absurd {
-            None
-        } else {
-            let (ty, new_tcs) = simplify(tcs, ty)?;
-            let (term, new_tcs) = check(new_tcs, &body, &ty)?;
-            let (term, new_tcs) = term.ast.inline_meta(new_tcs)?;
-            tcs = new_tcs;
-            Some(term)
+        let body = match (has_absurd, body) {
+            (true, None) => None,
+            (false, Some(body)) => {
+                let (term, new_tcs) = check_term(tcs, &body, ty)?;
+                let mut new_tcs = solve_level_bounds(new_tcs)?;
+                new_tcs.check_constraints()?;
+                let (term, new_tcs) = term.ast.inline_meta(new_tcs)?;
+                tcs = new_tcs;
+                Some(term)
+            }
+            (true, Some(..)) => return Err(TCE::AbsurdBody(source)),
+            (false, None) => return Err(TCE::MissingBody(source)),
         };
         let clause = Clause {
+            source,
             pat_tele,
             patterns,
             body,
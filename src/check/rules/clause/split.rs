@@ -1,29 +1,173 @@
-use voile_util::{tags::VarRec, uid::DBI};
+use std::rc::Rc;
+
+use voile_util::{
+    tags::VarRec,
+    uid::{next_uid, DBI, GI},
+};
 
 use crate::{
     check::{
-        monad::{TCE, TCMS, TCS},
+        monad::{TCE, TCM, TCMS, TCS},
+        pats::{CorePat, PatSubst},
         rules::{
-            clause::state::{progress_lhs_state, LhsState},
+            clause::{
+                eqs::Equation,
+                state::{progress_lhs_state, LhsState, Problem},
+                unify::{unify, Unification, UnifyEq},
+            },
+            simplify_ref,
             term::expect_data,
         },
     },
     syntax::{
         abs::AbsPat,
         common::ConHead,
-        core::{Bind, Decl, Tele, Val::Data},
-        pat::Copat,
+        core::{
+            subst::{DeBruijn, RedEx, Subst},
+            Bind, ConsInfo, Decl, Elim, Tele, TeleS, Term, Val,
+            Val::Data,
+        },
+        pat::{Copat, Pat},
     },
 };
 
-fn split_tele(mut tele: Tele, DBI(til): DBI) -> (Tele, Bind, Tele) {
-    debug_assert!(tele.len() > til + 1);
-    let pos = tele.len() - til + 1;
+/// Split $\Delta_1, x, \Delta_2$ into $(\Delta_1, x, \Delta_2)$,
+/// where `til` is the index of $x$.
+pub(super) fn split_tele(mut tele: Tele, DBI(til): DBI) -> (Tele, Bind, Tele) {
+    debug_assert!(tele.len() > til);
+    let pos = tele.len() - til - 1;
     let delta2 = tele.split_off(pos + 1);
-    let dom = tele.remove(tele.len() - 1);
+    let dom = tele.remove(pos);
     (tele, dom, delta2)
 }
 
+/// Instantiate the constructor telescope with the data parameters.
+pub(super) fn cons_tele(params: &TeleS, data_args: Vec<Term>) -> Tele {
+    let param_subst = Subst::parallel(data_args.into_iter().rev());
+    (params.iter().cloned().enumerate())
+        .map(|(i, bind)| bind.reduce_dbi(param_subst.clone().lift_by(DBI(i))))
+        .collect()
+}
+
+/// Split the arguments of the datatype `data` into the parameters
+/// and the indices.
+pub(super) fn data_args(tcs: &TCS, data: GI, mut args: Vec<Term>) -> (Vec<Term>, Vec<Term>) {
+    let param_len = match tcs.def(data) {
+        Decl::Data(info) => info.params.len(),
+        _ => unreachable!(),
+    };
+    let indices = args.split_off(param_len);
+    (args, indices)
+}
+
+/// The equations between the indices the constructor `cons` returns and
+/// the `indices` of the datatype being split, in the context
+/// $\Delta_1, \Theta$ where $\Theta$ is the constructor telescope.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Coverage.html#computeNeighbourhood).
+pub(super) fn index_eqs(
+    tcs: &TCS,
+    cons: &ConsInfo,
+    params: &[Term],
+    indices: Vec<Term>,
+) -> Vec<UnifyEq> {
+    let index_tele = match tcs.def(cons.data) {
+        Decl::Data(info) => &info.indices,
+        _ => unreachable!(),
+    };
+    let raise = Subst::raise(DBI(cons.params.len()));
+    let cons_subst =
+        (Subst::parallel(params.iter().rev().cloned())).lift_by(DBI(cons.params.len()));
+    // The parameters and the indices so far, for the types of the indices.
+    let mut args = params.to_vec();
+    (index_tele.iter().zip(&cons.indices).zip(indices))
+        .map(|((bind, cons_index), index)| {
+            let ty = (bind.ty.clone()).reduce_dbi(Subst::parallel(args.iter().rev().cloned()));
+            args.push(index.clone());
+            let lhs = cons_index.clone().reduce_dbi(cons_subst.clone());
+            let rhs = index.reduce_dbi(raise.clone());
+            UnifyEq::new(lhs, rhs, ty.reduce_dbi(raise.clone()))
+        })
+        .collect()
+}
+
+/// Instantiate $x$ in $\Delta_1, x, \Delta_2$ with `pat`, which lives in
+/// $\Delta_1'$ where $\Delta_1' \vdash \sigma : \Delta_1$.
+/// Returns $\Delta_1', \Delta_2[\sigma, pat / x]$ and the substitution
+/// from the original telescope.
+fn inst_tele(
+    mut delta1: Tele,
+    sigma: Rc<PatSubst>,
+    pat: CorePat,
+    delta2: Tele,
+) -> (Tele, Rc<PatSubst>) {
+    let delta2_len = delta2.len();
+    let pat_rho = sigma.cons(pat);
+    let rho = pat_rho.to_term_subst();
+    let mut delta2 = (delta2.into_iter().enumerate())
+        .map(|(i, bind)| bind.reduce_dbi(rho.clone().lift_by(DBI(i))))
+        .collect();
+    delta1.append(&mut delta2);
+    (delta1, pat_rho.lift_by(DBI(delta2_len)))
+}
+
+/// The new telescope, the substitution from the original one and
+/// the unifier of the indices.
+pub(super) type ConSplit = (Tele, Rc<PatSubst>, Rc<PatSubst>);
+
+/// $$
+/// \Delta_1, (x : D~\overline{ps}~\overline{is}), \Delta_2 \Longrightarrow
+/// \Delta_1', \Delta_2[\tau, c~\overline{ys}[\tau] / x]
+/// $$
+/// where $\Theta$ is the constructor telescope, `eqs` are the
+/// [index equations](index_eqs) and $\Delta_1' \vdash \tau : \Delta_1, \Theta$
+/// is their unifier.
+pub(super) fn split_tele_con(
+    tcs: &TCS,
+    mut delta1: Tele,
+    theta: Tele,
+    eqs: Vec<UnifyEq>,
+    head: ConHead,
+    is_forced: bool,
+    delta2: Tele,
+) -> TCM<Result<ConSplit, TCE>> {
+    let theta_len = theta.len();
+    let ys = (0..theta_len)
+        .rev()
+        .map(DBI)
+        .map(Pat::Var)
+        .collect::<Vec<_>>();
+    delta1.extend(theta);
+    let (delta1, tau) = if eqs.is_empty() {
+        (delta1, Default::default())
+    } else {
+        match unify(tcs, delta1, eqs)? {
+            Unification::Yes(delta1, tau) => (delta1, tau),
+            Unification::No(e) => return Ok(Err(e)),
+        }
+    };
+    let pat = Pat::Cons(is_forced, head, ys.reduce_dbi(tau.clone()));
+    let sigma = tau.clone().drop_by(DBI(theta_len));
+    let (tele, rho) = inst_tele(delta1, sigma, pat, delta2);
+    Ok(Ok((tele, rho, tau)))
+}
+
+/// $$
+/// \Delta_1, (x : a =_A b), \Delta_2 \Longrightarrow
+/// \Delta_1', \Delta_2[\sigma, \text{refl} / x]
+/// $$
+/// where $\Delta_1' \vdash \sigma : \Delta_1$ is the unifier of $a$ and $b$.
+pub(super) fn split_tele_refl(
+    tcs: &TCS,
+    delta1: Tele,
+    eq: UnifyEq,
+    delta2: Tele,
+) -> TCM<Result<(Tele, Rc<PatSubst>), TCE>> {
+    match unify(tcs, delta1, vec![eq])? {
+        Unification::Yes(delta1, sigma) => Ok(Ok(inst_tele(delta1, sigma, Pat::Refl, delta2))),
+        Unification::No(e) => Ok(Err(e)),
+    }
+}
+
 /// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.html#local-6989586621681883972).
 pub(super) fn split_proj(tcs: TCS, lhs: LhsState, proj: String) -> TCMS<LhsState> {
     let (data, tcs) = expect_data(tcs, lhs.target)?;
@@ -42,9 +186,10 @@ pub(super) fn split_proj(tcs: TCS, lhs: LhsState, proj: String) -> TCMS<LhsState
         Decl::Proj(info) => info.clone(),
         _ => unreachable!(),
     };
-    let target = proj_info.ty;
-    // FIXME: It might be of a function type taking a `self` parameter,
-    // we should apply it to `target`.
+    // The record value is the function applied to the patterns so far.
+    let elims = lhs.pats.iter().cloned().map(Elim::from).collect();
+    let record = lhs.def.clone().apply_elim(elims);
+    let target = proj_info.instantiate(data.args, record);
     let mut pats = lhs.pats;
     pats.push(Copat::Proj(proj));
     // `lhs.problem.take_first_todo_pat()` has
@@ -55,29 +200,122 @@ pub(super) fn split_proj(tcs: TCS, lhs: LhsState, proj: String) -> TCMS<LhsState
         ..lhs
     };
 
-    Ok((progress_lhs_state(lhs)?, tcs))
+    progress_lhs_state(tcs, lhs)
 }
 
+/// Split the variable `ix` into the constructor `head` applied to
+/// fresh pattern variables, the user patterns `pats` become new equations
+/// in place of the `eq_ix`-th one.
+///
+/// $$
+/// \Delta_1, (x : D~\overline{ps}), \Delta_2 \Longrightarrow
+/// \Delta_1', \Delta_2[\tau, c~\overline{ys}[\tau] / x]
+/// $$
 /// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.html#local-6989586621683054881).
 pub(super) fn split_con(
     tcs: TCS,
     ix: DBI,
+    eq_ix: usize,
     lhs: LhsState,
     is_forced: bool,
     head: ConHead,
     pats: Vec<AbsPat>,
 ) -> TCMS<LhsState> {
-    let (mut delta1, dom, delta2) = split_tele(lhs.tele, ix);
+    let (tele, dom, delta2) = split_tele(lhs.tele, ix);
     let (data, tcs) = expect_data(tcs, dom.ty)?;
-    // For disambiguation:
-    // tcs.under(&mut delta1, |tcs| {
-    //     unimplemented!()
-    // })?;
-    let cons_params = match tcs.def(head.cons_ix) {
-        Decl::Cons(c) => c.params.to_vec(),
-        _ => unreachable!(),
-    };
     // Agda checks if we're splitting on non-eta records as we
     // shouldn't split on lazy (non-eta) constructor.
-    unimplemented!()
+    if data.kind != VarRec::Variant {
+        return Err(TCE::not_data(Data(data)));
+    }
+    let cons = match tcs.def(head.cons_ix) {
+        Decl::Cons(c) => c,
+        _ => unreachable!(),
+    };
+    if cons.data != data.def {
+        let data_name = tcs.def(data.def).def_name().clone();
+        return Err(TCE::DifferentConsData(head.name, data_name));
+    }
+    let cons_head = cons.head(head.cons_ix);
+    let (params, indices) = data_args(&tcs, data.def, data.args);
+    let eqs = index_eqs(&tcs, cons, &params, indices);
+    let theta = cons_tele(&cons.params, params);
+    let theta_len = theta.len();
+    let delta2_len = delta2.len();
+
+    let explicit_len = theta.iter().filter(|bind| !bind.is_implicit()).count();
+    if explicit_len != pats.len() {
+        return Err(TCE::ConsArity(head.name, explicit_len, pats.len()));
+    }
+    let mut pats = pats.into_iter();
+    let sub_pats = (theta.iter())
+        .map(|bind| {
+            if bind.is_implicit() {
+                Pat::Var(unsafe { next_uid() })
+            } else {
+                pats.next().unwrap()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // $\rho = \text{Lift}_{\Delta_2} (c~\overline{ys}[\tau], \tau~\text{Weak}_\Theta \text{IdS})$.
+    let split = split_tele_con(&tcs, tele, theta.clone(), eqs, cons_head, is_forced, delta2)?;
+    let (tele, pat_rho, tau) = split?;
+    let rho = pat_rho.to_term_subst();
+    // From $\Delta_1, \Theta$ to the new telescope.
+    let tau = tau.to_term_subst().weaken(DBI(delta2_len));
+
+    let sub_eqs =
+        (sub_pats.into_iter().zip(theta.iter()).enumerate()).map(|(i, (pat, bind))| Equation {
+            in_pat: Copat::App(pat),
+            inst: Term::from_dbi(DBI(theta_len - i - 1)).reduce_dbi(tau.clone()),
+            ty: (bind.ty.clone())
+                .reduce_dbi(Subst::raise(DBI(theta_len - i)))
+                .reduce_dbi(tau.clone()),
+        });
+    let mut equations = lhs.problem.equations.reduce_dbi(rho.clone());
+    equations.splice(eq_ix..eq_ix, sub_eqs);
+
+    let lhs = LhsState {
+        tele,
+        pats: lhs.pats.reduce_dbi(pat_rho),
+        problem: Problem {
+            equations,
+            ..lhs.problem
+        },
+        target: lhs.target.reduce_dbi(rho),
+        def: lhs.def,
+    };
+    Ok((lhs, tcs))
+}
+
+/// Split the variable `ix` of an identity type into `refl`, by unifying
+/// the two endpoints in the telescope before it.
+///
+/// $$
+/// \Delta_1, (x : a =_A b), \Delta_2 \Longrightarrow
+/// \Delta_1', \Delta_2[\sigma, \text{refl} / x]
+/// $$
+/// where $\Delta_1' \vdash \sigma : \Delta_1$ is the unifier of $a$ and $b$.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.html).
+pub(super) fn split_refl(tcs: TCS, ix: DBI, lhs: LhsState) -> TCMS<LhsState> {
+    let (tele, dom, delta2) = split_tele(lhs.tele, ix);
+    let (ty, a, b) = match simplify_ref(&tcs, dom.ty)? {
+        Val::Id(ty, a, b) => (ty, a, b),
+        e => return Err(TCE::not_id(e)),
+    };
+    let eq = UnifyEq::new(*a, *b, *ty);
+    let (tele, pat_rho) = split_tele_refl(&tcs, tele, eq, delta2)??;
+    let rho = pat_rho.to_term_subst();
+    let lhs = LhsState {
+        tele,
+        pats: lhs.pats.reduce_dbi(pat_rho),
+        problem: Problem {
+            equations: lhs.problem.equations.reduce_dbi(rho.clone()),
+            ..lhs.problem
+        },
+        target: lhs.target.reduce_dbi(rho),
+        def: lhs.def,
+    };
+    Ok((lhs, tcs))
 }
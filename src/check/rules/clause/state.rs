@@ -1,14 +1,21 @@
-use voile_util::uid::DBI;
+use voile_util::uid::{next_uid, DBI};
 
 use crate::{
-    check::{monad::TCM, pats::CoreCopat, rules::clause::eqs::Equation},
+    check::{
+        monad::{MetaOrigin, TCE, TCM, TCMS, TCS},
+        pats::{CoreCopat, PatSubst},
+        rules::{
+            clause::eqs::Equation,
+            term::{meta_to_pi, simplify_ref},
+        },
+    },
     syntax::{
-        abs::AbsCopat,
+        abs::{AbsCopat, AbsPat},
         core::{
             subst::{DeBruijn, RedEx, Subst},
-            Tele, TeleS, Term,
+            Tele, TeleS, Term, Val,
         },
-        pat::PatCommon,
+        pat::{Copat, Pat, PatCommon},
     },
 };
 
@@ -54,6 +61,9 @@ pub(super) struct LhsState {
     pub(super) problem: Problem,
     /// Type eliminated by `problem`.
     pub(super) target: Term,
+    /// The function being defined, which is the record value
+    /// when applied to `pats`.
+    pub(super) def: Term,
 }
 
 impl LhsState {
@@ -63,39 +73,89 @@ impl LhsState {
     }
 
     /// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.ProblemRest.html#initLHSState).
-    pub(super) fn new(todo_pats: Vec<AbsCopat>, ty: Term) -> Self {
+    pub(super) fn new(todo_pats: Vec<AbsCopat>, ty: Term, def: Term) -> Self {
         Self {
             tele: Default::default(),
             pats: Default::default(),
             problem: Problem::new(todo_pats),
             target: ty,
+            def,
         }
     }
 }
 
+/// Match the patterns with the parameters in `tele`, inserting fresh variables
+/// for the implicit parameters without user-written implicit patterns.
+/// Returns the patterns of the introduced parameters and the rest of the patterns.
 /// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.Implicit.html#insertImplicitPatterns).
-#[allow(dead_code)]
-fn insert_implicit_pats(pats: Vec<AbsCopat>, tele: &TeleS) -> Vec<AbsCopat> {
-    debug_assert!(pats.len() <= tele.len());
-    let mut new_pats = Vec::with_capacity(tele.len().max(pats.capacity()));
+fn insert_implicit_pats(
+    tcs: &TCS,
+    pats: Vec<AbsCopat>,
+    tele: &TeleS,
+) -> TCM<(Vec<AbsCopat>, Vec<AbsCopat>)> {
+    let mut pats_iter = pats.into_iter().peekable();
+    let mut new_pats = Vec::with_capacity(tele.len());
     for bind in tele {
-        if bind.is_implicit() {
-            new_pats.push(AbsCopat::fresh_var())
-        } else {
-            break;
-        }
+        let pat = match pats_iter.peek() {
+            Some(Copat::Implicit(name, _))
+                if bind.is_implicit() && tcs.is_named(bind.name, name) =>
+            {
+                match pats_iter.next() {
+                    Some(Copat::Implicit(_, pat)) => Copat::App(pat),
+                    _ => unreachable!(),
+                }
+            }
+            // Either there's no implicit pattern,
+            // or it's for a later parameter
+            _ if bind.is_implicit() => AbsCopat::fresh_var(),
+            Some(Copat::App(..)) => pats_iter.next().unwrap(),
+            Some(Copat::Implicit(name, _)) => return Err(TCE::UnexpectedImplicit(name.clone())),
+            // All patterns are eliminated -- because
+            // `pats_iter.next()` returns `None` (or a projection)
+            Some(Copat::Proj(..)) | None => break,
+        };
+        new_pats.push(pat);
+    }
+    match pats_iter.peek() {
+        Some(Copat::Implicit(name, _)) => Err(TCE::UnexpectedImplicit(name.clone())),
+        _ => Ok((new_pats, pats_iter.collect())),
     }
-    // Shouldn't insert too many patterns
-    debug_assert!(new_pats.len() + pats.len() <= tele.len());
-    let mut original_pats = pats;
-    new_pats.append(&mut original_pats);
-    new_pats
 }
 
-/// It's supposed to use [`insert_implicit_pats`](self::insert_implicit_pats) to
-/// insert patterns, but as I'm refactoring it to an imperative style, reusing
-/// the code becomes a bit difficult. [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.ProblemRest.html#updateProblemRest).
+/// The function type `target` (in the context `gamma`) eliminated by the
+/// pattern `pat`, or `None` if it's not a function type.
+/// An unsolved meta is solved to a function type of fresh metas, which are
+/// inferred from the clauses, like when there's no signature.
+fn target_pi(
+    tcs: TCS,
+    gamma: &mut Tele,
+    target: &Term,
+    pat: &AbsPat,
+    def: &Term,
+) -> TCMS<Option<Term>> {
+    tcs.under(gamma, |tcs| match simplify_ref(&tcs, target.clone()) {
+        Ok(pi @ Val::Pi(..)) => Ok((Some(Term::Whnf(pi)), tcs)),
+        Ok(Val::Meta(mi, elims)) if elims.is_empty() => {
+            let name = match pat {
+                Pat::Var(uid) => *uid,
+                _ => unsafe { next_uid() },
+            };
+            let fun = match def {
+                Term::Redex(_, ident, _) => ident.text.clone(),
+                _ => unreachable!(),
+            };
+            let origin = MetaOrigin::ParamType(tcs.name_of(name).cloned(), fun);
+            let loc = tcs.current_loc;
+            meta_to_pi(tcs, mi, name, loc, origin)
+        }
+        Ok(..) | Err(TCE::Blocked(..)) => Ok((None, tcs)),
+        Err(e) => Err(e),
+    })
+}
+
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.ProblemRest.html#updateProblemRest).
 pub(super) fn progress_lhs_state(
+    mut tcs: TCS,
     LhsState {
         pats,
         problem: Problem {
@@ -104,47 +164,57 @@ pub(super) fn progress_lhs_state(
         },
         target,
         tele: mut old_tele,
+        def,
     }: LhsState,
-) -> TCM<LhsState> {
-    let mut pats_iter = todo_pats.into_iter();
-    let (mut tele, target) = target.tele_view();
-    let tele_len = tele.len();
+) -> TCMS<LhsState> {
+    let (mut tele, mut target) = target.tele_view();
+    let (mut in_pats, mut todo_pats) = insert_implicit_pats(&tcs, todo_pats, &tele)?;
+    // The rest of the parameters are revealed by evaluating the target.
+    while let (true, Some(Copat::App(pat))) = (in_pats.len() == tele.len(), todo_pats.first()) {
+        let mut gamma = old_tele.iter().chain(&tele).cloned().collect();
+        let (pi, new_tcs) = target_pi(tcs, &mut gamma, &target, pat, &def)?;
+        tcs = new_tcs;
+        let (mut more, ret) = match pi {
+            Some(pi) => pi.tele_view(),
+            None => break,
+        };
+        let (mut more_pats, rest) = insert_implicit_pats(&tcs, todo_pats, &more)?;
+        tele.append(&mut more);
+        in_pats.append(&mut more_pats);
+        target = ret;
+        todo_pats = rest;
+    }
+    let tele_len = in_pats.len();
+    // The rest of the parameters are not introduced by this clause,
+    // like when the clause body is a lambda.
+    let target = Term::pi_from_tele(tele.split_off(tele_len), target);
     let mut new_equations = Vec::with_capacity(tele_len);
-    for (i, bind) in tele.iter().enumerate() {
-        let mut f = |in_pat: AbsCopat| {
-            let equation = Equation {
-                in_pat,
-                // DBI is from right to left
-                inst: Term::from_dbi(DBI(tele_len - i - 1)),
-                ty: bind.ty.clone(),
-            };
-            new_equations.push(equation);
+    for (i, (bind, in_pat)) in tele.iter().zip(in_pats).enumerate() {
+        let equation = Equation {
+            in_pat,
+            // DBI is from right to left
+            inst: Term::from_dbi(DBI(tele_len - i - 1)),
+            // Like `inst`, in the context of the whole telescope
+            ty: (bind.ty.clone()).reduce_dbi(Subst::raise(DBI(tele_len - i))),
         };
-        if bind.is_implicit() {
-            f(AbsCopat::fresh_var());
-        } else if let Some(pat) = pats_iter.next() {
-            f(pat);
-        } else {
-            // All patterns are eliminated -- because
-            // `pats_iter.next()` returns `None`
-            break;
-        }
+        new_equations.push(equation);
     }
     let tau = Subst::raise(DBI(tele_len));
-    let mut equations = equations.reduce_dbi(tau.clone());
+    let mut equations = equations.reduce_dbi(tau);
     equations.append(&mut new_equations);
     let problem = Problem {
-        todo_pats: pats_iter.collect(),
+        todo_pats,
         equations,
     };
     old_tele.append(&mut tele);
-    let mut pats = pats.reduce_dbi(tau);
+    let mut pats = pats.reduce_dbi(PatSubst::raise(DBI(tele_len)));
     pats.extend((0..tele_len).rev().map(DBI).map(CoreCopat::var));
     let state = LhsState {
         tele: old_tele,
         pats,
         problem,
         target,
+        def,
     };
-    Ok(state)
+    Ok((state, tcs))
 }
@@ -0,0 +1,341 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+use voile_util::uid::{DBI, GI};
+
+use crate::{
+    check::{
+        monad::{TCE, TCM, TCS},
+        pats::CorePat,
+        rules::{
+            clause::{
+                coverage::app_pats,
+                guardedness::{guards, projections},
+            },
+            simplify_ref,
+        },
+    },
+    syntax::{
+        core::{Closure, Decl, Elim, FuncInfo, Term, Val},
+        pat::Pat,
+    },
+};
+
+/// How an argument of a call is related to a parameter of the caller.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.Termination.Order.html#Order).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+enum Order {
+    Unknown,
+    /// Less than or equal to.
+    Le,
+    /// Strictly (structurally) smaller.
+    Lt,
+}
+
+impl Order {
+    /// Composition along a call path.
+    fn then(self, other: Self) -> Self {
+        use Order::*;
+        match (self, other) {
+            (Unknown, _) | (_, Unknown) => Unknown,
+            (Lt, _) | (_, Lt) => Lt,
+            (Le, Le) => Le,
+        }
+    }
+}
+
+/// `m[j][i]` is how the `j`-th argument of the call is related to the
+/// `i`-th parameter of the caller, so the caller and the callee may have
+/// different numbers of parameters.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.Termination.CallMatrix.html#CallMatrix).
+type CallMatrix = Vec<Vec<Order>>;
+
+/// The call matrix of `a` followed by `b`.
+fn compose(a: &CallMatrix, b: &CallMatrix) -> CallMatrix {
+    (b.iter())
+        .map(|row| {
+            (0..a[0].len())
+                .map(|i| {
+                    (row.iter().zip(a))
+                        .map(|(b_kj, a_j)| a_j[i].then(*b_kj))
+                        .max()
+                        .unwrap_or(Order::Unknown)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// A recursive call in the body of a clause of `caller`.
+struct Call {
+    caller: GI,
+    clause: usize,
+    callee: GI,
+    term: Term,
+    matrix: CallMatrix,
+}
+
+fn occurs(x: DBI, pat: &CorePat) -> bool {
+    match pat {
+        Pat::Var(y) => x == *y,
+        Pat::Cons(_, _, pats) => pats.iter().any(|pat| occurs(x, pat)),
+        Pat::Forced(..) | Pat::Refl | Pat::Absurd => false,
+    }
+}
+
+/// How `arg`, under `depth` binders in the clause body, is related to `pat`.
+fn order(tcs: &TCS, arg: &Term, depth: usize, pat: &CorePat) -> Order {
+    // Constructor calls are not yet in whnf, but we shouldn't unfold
+    // function calls here, they may not terminate.
+    let arg = match arg {
+        Term::Redex(gi, ..) => match tcs.def(*gi) {
+            Decl::Cons(..) => match simplify_ref(tcs, arg.clone()) {
+                Ok(val) => Term::Whnf(val),
+                Err(..) => return Order::Unknown,
+            },
+            _ => return Order::Unknown,
+        },
+        arg => arg.clone(),
+    };
+    match (&arg, pat) {
+        (Term::Whnf(Val::Var(DBI(x), elims)), pat) if elims.is_empty() => {
+            match x.checked_sub(depth) {
+                Some(x) if *pat == Pat::Var(DBI(x)) => Order::Le,
+                Some(x) if occurs(DBI(x), pat) => Order::Lt,
+                _ => Order::Unknown,
+            }
+        }
+        (Term::Whnf(Val::Cons(c, args)), Pat::Cons(_, d, pats))
+            if c.cons_ix == d.cons_ix && args.len() == pats.len() =>
+        {
+            (args.iter().zip(pats))
+                .map(|(arg, pat)| order(tcs, arg, depth, pat).min(Order::Le))
+                .min()
+                .unwrap_or(Order::Le)
+        }
+        (Term::Whnf(Val::Refl), Pat::Refl) => Order::Le,
+        _ => Order::Unknown,
+    }
+}
+
+/// The postfix form `r .proj ..` of a prefix projection `proj r ..` on a
+/// call `r`, as computed by `whnf`, with the number of codata parameters.
+fn postfix_proj(tcs: &TCS, def: GI, elims: &[Elim]) -> Option<(usize, Term)> {
+    let proj = match tcs.def(def) {
+        Decl::Proj(proj) => proj,
+        _ => return None,
+    };
+    let params_len = match tcs.def(proj.codata) {
+        Decl::Codata(info) => info.params.len(),
+        _ => unreachable!(),
+    };
+    let (record, rest) = elims.get(params_len..)?.split_first()?;
+    let record = match record {
+        Elim::App(record) if matches!(**record, Term::Redex(..)) => (**record).clone(),
+        _ => return None,
+    };
+    let mut elims = vec![Elim::Proj(proj.name.text.clone())];
+    elims.extend_from_slice(rest);
+    Some((params_len, record.apply_elim(elims)))
+}
+
+/// Collect the calls (to any definition) in `term`, with the depth of binders.
+/// Prefix projections of calls are seen as postfix ones, so that `tail xs`
+/// is a call to `xs` observed by one projection.
+pub(super) fn collect_calls(
+    tcs: &TCS,
+    term: &Term,
+    depth: usize,
+    f: &mut impl FnMut(GI, &Term, usize),
+) {
+    let visit_elims = |elims: &[Elim], f: &mut _| {
+        for elim in elims {
+            if let Elim::App(a) = elim {
+                collect_calls(tcs, a, depth, f);
+            }
+        }
+    };
+    let val = match term {
+        Term::Whnf(val) => val,
+        Term::Redex(gi, _, elims) => {
+            if let Some((params_len, term)) = postfix_proj(tcs, *gi, elims) {
+                visit_elims(&elims[..params_len], f);
+                return collect_calls(tcs, &term, depth, f);
+            }
+            f(*gi, term, depth);
+            return visit_elims(elims, f);
+        }
+    };
+    match val {
+        Val::Var(_, elims) | Val::Meta(_, elims) => visit_elims(elims, f),
+        Val::Cons(_, args) => args.iter().for_each(|a| collect_calls(tcs, a, depth, f)),
+        Val::Data(info) => (info.args.iter()).for_each(|a| collect_calls(tcs, a, depth, f)),
+        Val::Pi(bind, Closure::Plain(body)) | Val::Lam(bind, Closure::Plain(body)) => {
+            collect_calls(tcs, &bind.ty, depth, f);
+            collect_calls(tcs, body, depth + 1, f);
+        }
+        Val::Id(ty, a, b) => {
+            collect_calls(tcs, ty, depth, f);
+            collect_calls(tcs, a, depth, f);
+            collect_calls(tcs, b, depth, f);
+        }
+        Val::Type(l) | Val::Level(l) => l.atoms().for_each(|a| collect_calls(tcs, a, depth, f)),
+        Val::LevelType | Val::Axiom(..) | Val::Refl => {}
+    }
+}
+
+fn func(tcs: &TCS, def: GI) -> &FuncInfo {
+    match tcs.def(def) {
+        Decl::Func(func) => func,
+        _ => unreachable!(),
+    }
+}
+
+/// The number of parameters matched by the clauses.
+fn arity(func: &FuncInfo) -> usize {
+    (func.clauses.iter().map(|c| app_pats(c).len()))
+        .max()
+        .unwrap_or_default()
+}
+
+/// The functions called by the function `def`, including itself.
+fn callees(tcs: &TCS, def: GI) -> Vec<GI> {
+    let mut callees = Vec::new();
+    for body in func(tcs, def).clauses.iter().flat_map(|c| &c.body) {
+        collect_calls(tcs, body, 0, &mut |gi, _, _| {
+            if matches!(tcs.def(gi), Decl::Func(..)) && !callees.contains(&gi) {
+                callees.push(gi);
+            }
+        });
+    }
+    callees
+}
+
+/// The functions in `unchecked` which are (mutually) called by `def`, which
+/// are termination-checked together, or `None` if some of the called
+/// functions are not yet completely checked (`is_complete`), like the
+/// forward-declared ones.
+pub fn mutual_group(
+    tcs: &TCS,
+    def: GI,
+    unchecked: &[GI],
+    is_complete: impl Fn(GI) -> bool,
+) -> Option<Vec<GI>> {
+    let mut group = vec![def];
+    let mut next = 0;
+    while next < group.len() {
+        for callee in callees(tcs, group[next]) {
+            if !is_complete(callee) {
+                return None;
+            }
+            if unchecked.contains(&callee) && !group.contains(&callee) {
+                group.push(callee);
+            }
+        }
+        next += 1;
+    }
+    Some(group)
+}
+
+/// Check if the recursive calls of the (mutually recursive) functions
+/// `defs` are terminating, using the size-change principle.
+/// Projection copatterns also count as decreasing, see
+/// [`guardedness`](self::super::guardedness).
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.Termination.Termination.html#terminates).
+pub fn termination(tcs: &TCS, defs: &[GI]) -> TCM<()> {
+    // The last parameter is the number of projections observing the call.
+    let dims = |def| arity(func(tcs, def)) + 1;
+    let mut calls = Vec::new();
+    for &caller in defs {
+        let func = func(tcs, caller);
+        let caller_dims = dims(caller);
+        for (i, clause) in func.clauses.iter().enumerate() {
+            let body = match &clause.body {
+                Some(body) => body,
+                None => continue,
+            };
+            let pats = app_pats(clause);
+            let guards = guards(clause);
+            collect_calls(tcs, body, 0, &mut |callee, term, depth| {
+                if !defs.contains(&callee) {
+                    return;
+                }
+                let elims = match term {
+                    Term::Redex(_, _, elims) => elims,
+                    _ => unreachable!(),
+                };
+                let args = (elims.iter().take_while(|elim| !elim.is_proj()))
+                    .map(|elim| match elim {
+                        Elim::App(a) => &**a,
+                        Elim::Proj(..) => unreachable!(),
+                    })
+                    .collect::<Vec<_>>();
+                let observed = projections(elims);
+                let callee_dims = dims(callee);
+                let matrix = (0..callee_dims)
+                    .map(|j| {
+                        (0..caller_dims)
+                            .map(|i| match (args.get(j), pats.get(i)) {
+                                (Some(arg), Some(pat)) => order(tcs, arg, depth, pat),
+                                _ if i + 1 == caller_dims && j + 1 == callee_dims => {
+                                    match observed.cmp(&guards) {
+                                        Ordering::Less => Order::Lt,
+                                        Ordering::Equal => Order::Le,
+                                        Ordering::Greater => Order::Unknown,
+                                    }
+                                }
+                                _ => Order::Unknown,
+                            })
+                            .collect()
+                    })
+                    .collect();
+                calls.push(Call {
+                    caller,
+                    clause: i,
+                    callee,
+                    term: term.clone(),
+                    matrix,
+                });
+            });
+        }
+    }
+    // The transitive closure of the calls, with the call paths.
+    let mut closure = (calls.iter().enumerate())
+        .map(|(i, call)| (call.caller, call.callee, call.matrix.clone(), vec![i]))
+        .collect::<Vec<_>>();
+    let mut seen = (closure.iter())
+        .map(|(from, to, m, _)| (*from, *to, m.clone()))
+        .collect::<HashSet<_>>();
+    let mut next = 0;
+    while next < closure.len() {
+        for (i, call) in calls.iter().enumerate() {
+            let (from, to) = (closure[next].0, closure[next].1);
+            if call.caller != to {
+                continue;
+            }
+            let matrix = compose(&closure[next].2, &call.matrix);
+            if seen.insert((from, call.callee, matrix.clone())) {
+                let mut path = closure[next].3.clone();
+                path.push(i);
+                closure.push((from, call.callee, matrix, path));
+            }
+        }
+        next += 1;
+    }
+    // Every idempotent call path from a function to itself
+    // should decrease some parameter.
+    let bad = closure.into_iter().find(|(from, to, m, _)| {
+        from == to && compose(m, m) == *m && !(0..m.len()).any(|i| m[i][i] == Order::Lt)
+    });
+    match bad {
+        None => Ok(()),
+        Some((from, _, _, path)) => {
+            let path = (path.into_iter().map(|i| &calls[i]))
+                .map(|call| {
+                    let clause = &func(tcs, call.caller).clauses[call.clause];
+                    (clause.source, call.term.clone())
+                })
+                .collect();
+            Err(TCE::NonTerminating(func(tcs, from).name.clone(), path))
+        }
+    }
+}
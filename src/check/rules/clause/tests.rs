@@ -0,0 +1,776 @@
+use voile_util::{
+    loc::Ident,
+    tags::Plicit,
+    uid::{next_uid, DBI, GI},
+};
+
+use crate::{
+    check::{
+        monad::{MetaOrigin, MetaSol, TCE, TCM, TCS, TCW},
+        rules::{
+            check_decls,
+            clause::{
+                guardedness, termination,
+                unify::{unify, Unification, UnifyEq},
+            },
+            term::{self, is_eta_var},
+        },
+    },
+    syntax::{
+        abs::desugar::desugar_main,
+        core::{
+            subst::DeBruijn, Bind, CaseTree, Clause, Closure, Decl, Elim, FuncInfo, Level, Term,
+            Val,
+        },
+        pat::{Copat, Pat},
+        surf::parse_str,
+    },
+};
+
+const NAT: &str = "data Nat { constructor Zero; constructor Succ Nat; };";
+
+fn check_code(code: &str) -> TCM {
+    let desugar = desugar_main(parse_str(code).unwrap()).unwrap();
+    check_decls(TCS::considerate_of(&desugar), desugar.decls)
+}
+
+fn nat_tcs() -> TCS {
+    check_code(NAT).unwrap()
+}
+
+fn def(tcs: &TCS, ix: usize, args: Vec<Term>) -> Term {
+    let name = tcs.def(GI(ix)).def_name().clone();
+    Term::def(GI(ix), name, args.into_iter().map(Elim::app).collect())
+}
+
+fn nat_tele(tcs: &TCS, len: usize) -> Vec<Bind> {
+    (0..len)
+        .map(|_| Bind::new(Plicit::Ex, unsafe { next_uid() }, def(tcs, 0, vec![])))
+        .collect()
+}
+
+#[test]
+fn unify_injectivity_solution() {
+    let tcs = nat_tcs();
+    // x y : Nat |- Succ x = Succ (Succ y)
+    let tele = nat_tele(&tcs, 2);
+    let x = Term::from_dbi(DBI(1));
+    let y = Term::from_dbi(DBI(0));
+    let lhs = def(&tcs, 2, vec![x]);
+    let rhs = def(&tcs, 2, vec![def(&tcs, 2, vec![y])]);
+    let eq = UnifyEq::new(lhs, rhs, def(&tcs, 0, vec![]));
+    let (tele, sigma) = match unify(&tcs, tele, vec![eq]).unwrap() {
+        Unification::Yes(tele, sigma) => (tele, sigma),
+        Unification::No(e) => panic!("{}", e),
+    };
+    assert_eq!(tele.len(), 1);
+    assert_eq!(sigma.lookup(DBI(0)), Pat::Var(DBI(0)));
+    match sigma.lookup(DBI(1)) {
+        Pat::Forced(Term::Whnf(Val::Cons(head, args))) => {
+            assert_eq!(head.cons_ix, GI(2));
+            assert_eq!(args, vec![Term::from_dbi(DBI(0))]);
+        }
+        p => panic!("{:?}", p),
+    }
+}
+
+#[test]
+fn unify_conflict_cycle() {
+    let tcs = nat_tcs();
+    let tele = nat_tele(&tcs, 1);
+    let x = Term::from_dbi(DBI(0));
+    let nat = def(&tcs, 0, vec![]);
+    let zero = def(&tcs, 1, vec![]);
+    let succ_x = def(&tcs, 2, vec![x.clone()]);
+    let eq = UnifyEq::new(zero, succ_x.clone(), nat.clone());
+    match unify(&tcs, tele.clone(), vec![eq]).unwrap() {
+        Unification::No(TCE::UnifyConflict(..)) => {}
+        _ => panic!(),
+    }
+    let eq = UnifyEq::new(x, succ_x, nat);
+    match unify(&tcs, tele, vec![eq]).unwrap() {
+        Unification::No(TCE::UnifyCycle(..)) => {}
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn unify_without_k() {
+    let mut tcs = nat_tcs();
+    let tele = nat_tele(&tcs, 1);
+    let x = Term::from_dbi(DBI(0));
+    let nat = def(&tcs, 0, vec![]);
+    let zero = def(&tcs, 1, vec![]);
+    let succ_x = def(&tcs, 2, vec![x]);
+    let eq = UnifyEq::new(succ_x.clone(), succ_x.clone(), nat.clone());
+    match unify(&tcs, tele.clone(), vec![eq]).unwrap() {
+        Unification::Yes(tele, _) => assert_eq!(tele.len(), 1),
+        Unification::No(e) => panic!("{}", e),
+    }
+    tcs.without_k = true;
+    // Constructor equations are still fine
+    let eq = UnifyEq::new(zero.clone(), zero, nat.clone());
+    match unify(&tcs, tele.clone(), vec![eq]).unwrap() {
+        Unification::Yes(tele, _) => assert_eq!(tele.len(), 1),
+        Unification::No(e) => panic!("{}", e),
+    }
+    let eq = UnifyEq::new(succ_x.clone(), succ_x, nat);
+    match unify(&tcs, tele, vec![eq]) {
+        Err(TCE::UnifyReflexive(a, b, ty, _)) => {
+            assert_eq!(a, "x0");
+            assert_eq!(b, a);
+            assert_eq!(ty, "Nat");
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn coverage_case_tree() {
+    let code = "definition plus : Nat -> Nat -> Nat;\
+        clause plus Zero m = m;\
+        clause plus (Succ n) m = Succ (plus n m);";
+    let tcs = check_code(&format!("{}{}", NAT, code)).unwrap();
+    let tree = match tcs.def(GI(3)) {
+        Decl::Func(f) => f.case_tree.clone().unwrap(),
+        _ => panic!(),
+    };
+    let branches = match tree {
+        CaseTree::Case(0, branches) => branches,
+        t => panic!("{:?}", t),
+    };
+    assert_eq!(branches.len(), 2);
+    assert_eq!(
+        branches[0],
+        (GI(1), CaseTree::Done(1, Term::from_dbi(DBI(0))))
+    );
+    match &branches[1] {
+        (GI(2), CaseTree::Done(2, _)) => {}
+        b => panic!("{:?}", b),
+    }
+}
+
+#[test]
+fn coverage_missing() {
+    let code = "definition f : Nat -> Nat -> Nat;\
+        clause f Zero Zero = Zero;\
+        clause f (Succ (Succ n)) m = m;";
+    match check_code(&format!("{}{}", NAT, code)) {
+        Err(TCE::MissingClauses(_, cases)) => assert_eq!(
+            cases,
+            vec![vec!["Zero", "(Succ _)"], vec!["(Succ Zero)", "_"]]
+        ),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn coverage_unreachable() {
+    let code = "definition f : Nat -> Nat;\
+        clause f Zero = Zero;\
+        clause f n = n;\
+        clause f (Succ n) = n;";
+    let tcs = check_code(&format!("{}{}", NAT, code)).unwrap();
+    match &tcs.warnings[..] {
+        [TCW::UnreachableClauses(name, locs)] => {
+            assert_eq!(name.text, "f");
+            assert_eq!(locs.len(), 1);
+            assert_eq!(locs[0].line, 1);
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn coverage_evaluated_signature() {
+    let code = "definition T : Type;\
+        clause T = Nat -> Nat;\
+        definition f : Nat -> T;\
+        clause f Zero m = m;\
+        clause f (Succ n) Zero = n;";
+    match check_code(&format!("{}{}", NAT, code)) {
+        Err(TCE::MissingClauses(name, cases)) => {
+            assert_eq!(name.text, "f");
+            assert_eq!(cases.len(), 1);
+            assert_eq!(cases[0].len(), 2);
+        }
+        _ => panic!(),
+    }
+    let code = format!("{}{}clause f (Succ n) (Succ m) = m;", NAT, code);
+    check_code(&code).unwrap();
+}
+
+/// Copatterns are not covered, the missing projection clauses are stuck.
+#[test]
+fn coverage_copattern() {
+    let code = "codata Stream {\
+          projection head : Nat;\
+          projection tail : Stream;\
+        };\
+        definition zeros : Stream;\
+        clause zeros .head = Zero;";
+    let tcs = check_code(&format!("{}{}", NAT, code)).unwrap();
+    match tcs.def(GI(6)) {
+        Decl::Func(f) => assert_eq!(f.case_tree, None),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn termination_size_change() {
+    let code = "definition ack : Nat -> Nat -> Nat;\
+        clause ack Zero n = Succ n;\
+        clause ack (Succ m) Zero = ack m (Succ Zero);\
+        clause ack (Succ m) (Succ n) = ack m (ack (Succ m) n);\
+        definition swap : Nat -> Nat -> Nat;\
+        clause swap Zero n = n;\
+        clause swap (Succ m) n = swap n m;";
+    check_code(&format!("{}{}", NAT, code)).unwrap();
+    let code = "definition f : Nat -> Nat -> Nat;\
+        clause f Zero n = n;\
+        clause f (Succ m) n = f (Succ m) m;";
+    match check_code(&format!("{}{}", NAT, code)) {
+        Err(TCE::NonTerminating(name, calls)) => {
+            assert_eq!(name.text, "f");
+            assert_eq!(calls.len(), 1);
+        }
+        _ => panic!(),
+    }
+    // Mutual recursion, checked after the clauses of both functions.
+    let code = |odd: &str| {
+        format!(
+            "definition even : Nat -> Nat;\
+            definition odd : Nat -> Nat;\
+            clause even Zero = Succ Zero;\
+            clause even (Succ n) = odd n;\
+            clause odd Zero = Zero;\
+            clause odd (Succ n) = {};",
+            odd
+        )
+    };
+    check_code(&format!("{}{}", NAT, code("even n"))).unwrap();
+    match check_code(&format!("{}{}", NAT, code("even (Succ (Succ n))"))) {
+        Err(TCE::NonTerminating(name, calls)) => {
+            assert_eq!(name.text, "even");
+            assert_eq!(calls.len(), 2);
+        }
+        _ => panic!(),
+    }
+}
+
+/// `repeat : Nat -> Stream Nat`, defined by `clause repeat n .tail = body`.
+fn repeat_tcs(body: impl FnOnce(GI, Ident) -> Term) -> TCS {
+    let mut tcs = nat_tcs();
+    let name = Ident::new(Default::default(), "repeat".to_owned());
+    let clause = Clause {
+        source: Default::default(),
+        pat_tele: nat_tele(&tcs, 1),
+        patterns: vec![Copat::App(Pat::Var(DBI(0))), Copat::Proj("tail".to_owned())],
+        body: Some(body(GI(3), name.clone())),
+    };
+    tcs.sigma.push(Decl::Func(FuncInfo {
+        loc: Default::default(),
+        name,
+        signature: Term::universe(Level::default()),
+        clauses: vec![clause],
+        case_tree: None,
+    }));
+    tcs
+}
+
+#[test]
+fn guardedness_copattern() {
+    let n = || Elim::app(Term::from_dbi(DBI(0)));
+    // repeat n .tail = repeat n
+    let tcs = repeat_tcs(|gi, name| Term::def(gi, name, vec![n()]));
+    guardedness(&tcs, GI(3)).unwrap();
+    termination(&tcs, &[GI(3)]).unwrap();
+    // repeat n .tail = repeat n .tail
+    let tail = || Elim::Proj("tail".to_owned());
+    let tcs = repeat_tcs(|gi, name| Term::def(gi, name, vec![n(), tail()]));
+    match guardedness(&tcs, GI(3)) {
+        Err(TCE::NotGuarded(name, ..)) => assert_eq!(name.text, "repeat"),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn codata_dependent_projection() {
+    let code = "data Unit { constructor tt; };\
+        definition F : Nat -> Type;\
+        clause F Zero = Nat;\
+        clause F (Succ n) = Unit;\
+        codata Sigma (A : Type) (B : A -> Type) {\
+          projection fst : A;\
+          projection snd : B (self .fst);\
+        };\
+        definition p : Sigma Nat F;\
+        clause p .fst = Succ Zero;";
+    let snd = |body: &str| format!("{}{}clause p .snd = {};", NAT, code, body);
+    check_code(&snd("tt")).unwrap();
+    assert!(check_code(&snd("Zero")).is_err());
+}
+
+const PAIR: &str = "codata Pair (A : Type) (B : Type) {\
+      projection fst : A;\
+      projection snd : B;\
+    };";
+
+#[test]
+fn projection_implicit_params() {
+    let code = "definition p : Pair Nat Nat;\
+        clause p .fst = Zero;\
+        clause p .snd = Succ Zero;\
+        definition first : fst {A = Nat} {B = Nat} p = Zero;\
+        clause first = refl;\
+        definition second : snd {B = Nat} p = Succ Zero;\
+        clause second = refl;";
+    check_code(&format!("{}{}{}", NAT, PAIR, code)).unwrap();
+    let code = "definition p : Pair Nat Nat;\
+        clause p .fst = Zero;\
+        clause p .snd = Zero;\
+        definition bad : fst Nat p = Zero;";
+    assert!(check_code(&format!("{}{}{}", NAT, PAIR, code)).is_err());
+}
+
+#[test]
+fn eta_conversion() {
+    let code = "definition dup : Pair Nat Nat -> Pair Nat Nat;\
+        clause dup p .fst = p .fst;\
+        clause dup p .snd = p .snd;\
+        definition pair : (p : Pair Nat Nat) -> p = dup p;\
+        clause pair p = refl;\
+        definition fun : (f : Nat -> Nat) -> f = (\\x. f x);\
+        clause fun f = refl;";
+    check_code(&format!("{}{}{}", NAT, PAIR, code)).unwrap();
+    let code = "definition swap : Pair Nat Nat -> Pair Nat Nat;\
+        clause swap p .fst = p .snd;\
+        clause swap p .snd = p .fst;\
+        definition bad : (p : Pair Nat Nat) -> p = swap p;\
+        clause bad p = refl;";
+    assert!(check_code(&format!("{}{}{}", NAT, PAIR, code)).is_err());
+}
+
+const RECORD: &str = "codata Pair (A : Type) (B : Type) {\
+      constructor mk;\
+      projection fst : A;\
+      projection snd : B;\
+    };\
+    definition pick : Nat -> Pair Nat Nat;\
+    clause pick Zero = mk Zero Zero;\
+    clause pick (Succ n) = mk n n;";
+
+#[test]
+fn eta_var_expanded() {
+    let tcs = check_code(&format!("{}{}", NAT, RECORD)).unwrap();
+    let nat_ty = def(&tcs, 0, vec![]);
+    let nat = || nat_ty.clone();
+    let x = |i| Term::from_dbi(DBI(i));
+    // \y. x y : Nat -> Nat
+    let bind = Bind::new(Plicit::Ex, unsafe { next_uid() }, nat());
+    let ty = Term::pi2(bind.clone().boxed(), Closure::plain(nat()));
+    let lam = Term::lam(bind.boxed(), Closure::plain(x(1).apply(vec![x(0)])));
+    let (i, tcs) = is_eta_var(tcs, lam, ty).unwrap();
+    assert_eq!(i, Some(DBI(0)));
+    // mk (x .fst) (x .snd) : Pair Nat Nat
+    let mk = match tcs.def(GI(6)) {
+        Decl::Cons(cons) => cons.head(GI(6)),
+        _ => panic!(),
+    };
+    let proj = |t: Term, f: &str| t.apply_elim(vec![Elim::Proj(f.to_owned())]);
+    let eta = |t: Term| Term::cons(mk.clone(), vec![proj(t.clone(), "fst"), proj(t, "snd")]);
+    let ty = def(&tcs, 3, vec![nat(), nat()]);
+    let (i, tcs) = is_eta_var(tcs, eta(x(0)), ty.clone()).unwrap();
+    assert_eq!(i, Some(DBI(0)));
+    let swapped = Term::cons(mk.clone(), vec![proj(x(0), "snd"), proj(x(0), "fst")]);
+    let (i, tcs) = is_eta_var(tcs, swapped.clone(), ty).unwrap();
+    assert_eq!(i, None);
+    let tcs = term::unify(tcs, &eta(x(0)), &x(0)).unwrap();
+    // `pick x` is stuck, but still neutral.
+    let stuck = def(&tcs, 7, vec![x(0)]);
+    let tcs = term::unify(tcs, &eta(stuck.clone()), &stuck).unwrap();
+    assert!(term::unify(tcs, &swapped, &x(0)).is_err());
+}
+
+#[test]
+fn record_constructor() {
+    let code = "definition swap : {A B : Type} -> Pair A B -> Pair B A;\
+        clause swap p = mk (p .snd) (p .fst);\
+        definition swapSwap : (p : Pair Nat Nat) -> swap (swap p) = p;\
+        clause swapSwap p = refl;\
+        codata Sigma (A : Type) (B : A -> Type) {\
+          constructor pair;\
+          projection proj1 : A;\
+          projection proj2 : B (self .proj1);\
+        };\
+        definition zeros : Sigma Nat (\\n. n = Zero);\
+        clause zeros = pair {B = \\n. n = Zero} Zero refl;";
+    check_code(&format!("{}{}{}", NAT, RECORD, code)).unwrap();
+    let code = "codata Bad {\
+          constructor bad;\
+          projection n : Nat;\
+          projection same : self = self;\
+        };";
+    match check_code(&format!("{}{}", NAT, code)) {
+        Err(TCE::NotRecordCons(cons, field)) => {
+            assert_eq!(cons.text, "bad");
+            assert_eq!(field.text, "same");
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn miller_pattern() {
+    let app = |arg: &str| {
+        format!(
+            "definition app : {{P : Nat -> Type}} -> ((n : Nat) -> P {0}) -> (n : Nat) -> P {0};\
+            clause app f n = f n;\
+            definition use : ((n : Nat) -> n = n) -> (n : Nat) -> n = n;\
+            clause use f n = app f n;",
+            arg
+        )
+    };
+    check_code(&format!("{}{}", NAT, app("n"))).unwrap();
+    match check_code(&format!("{}{}", NAT, app("(Succ n)"))) {
+        Err(TCE::UnsolvedConstraints(cs)) => assert_eq!(cs.len(), 2),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn pattern_solution_binder() {
+    let mut tcs = nat_tcs();
+    tcs.enter_def(GI(3));
+    let nat = def(&tcs, 0, vec![]);
+    // ?P : Nat -> Type
+    let bind = || Bind::new(Plicit::Ex, unsafe { next_uid() }, nat.clone()).boxed();
+    let ty = Term::pi2(bind(), Closure::plain(Term::universe(Level::default())));
+    let meta = tcs.fresh_meta(ty, Default::default(), MetaOrigin::User("P".to_owned()));
+    // \x. ?P x = \x. Nat, where `x` is bound during the conversion check
+    let lhs = Term::lam(
+        bind(),
+        Closure::plain(meta.apply(vec![Term::from_dbi(DBI(0))])),
+    );
+    let rhs = Term::lam(bind(), Closure::plain(nat.clone()));
+    let tcs = term::unify(tcs, &lhs, &rhs).unwrap();
+    match tcs.meta_ctx().solution(Default::default()) {
+        MetaSol::Solved(_, sol) => match &**sol {
+            Term::Whnf(Val::Lam(bind, _)) => assert_eq!(*bind.ty, nat),
+            _ => panic!(),
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn postponed_constraint() {
+    let code = |g: &str| {
+        format!(
+            "{}definition app : {{P : Nat -> Type}} -> ((n : Nat) -> P (Succ n)) -> ((n : Nat) -> P n) -> Nat;\
+            clause app f g = Zero;\
+            definition use : ((n : Nat) -> Succ n = Succ n) -> ((n : Nat) -> {} = {}) -> Nat;\
+            clause use f g = app f g;",
+            NAT, g, g
+        )
+    };
+    check_code(&code("n")).unwrap();
+    // The constraint is woken up and fails.
+    match check_code(&code("Succ n")) {
+        Err(TCE::Wrapped(e, _)) => assert!(matches!(*e, TCE::DifferentTerm(..))),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn postponed_elims() {
+    let code = "definition T : Nat -> Type;\
+        clause T Zero = Nat;\
+        clause T (Succ n) = Nat -> Nat -> Nat;\
+        definition P : Nat -> Type;\
+        clause P Zero = Nat;\
+        clause P (Succ n) = Pair Nat Nat;\
+        definition app : {n : Nat} -> (T n -> Nat) -> n = Succ Zero -> Nat;\
+        clause app f eq = Zero;\
+        definition proj : {n : Nat} -> (P n -> Nat) -> n = Succ Zero -> Nat;\
+        clause proj f eq = Zero;\
+        definition arg : {n : Nat} -> T n -> n = Succ Zero -> Nat;\
+        clause arg t eq = Zero;\
+        definition use : Nat;\
+        clause use = app (\\f. f Zero Zero) refl;\
+        definition use2 : Nat;\
+        clause use2 = proj (\\p. p .fst) refl;\
+        definition use3 : Nat;\
+        clause use3 = arg (\\x. \\y. y) refl;\
+        definition same : (n m : Nat) -> n = m -> Nat;\
+        clause same n m refl = app (\\f. f n (f m Zero)) refl;";
+    check_code(&format!("{}{}{}", NAT, PAIR, code)).unwrap();
+    // The postponed application is still ill-typed once its head is known.
+    let code = code.replace("app (\\f. f Zero Zero)", "app (\\f. f f)");
+    match check_code(&format!("{}{}{}", NAT, PAIR, code)) {
+        Err(TCE::Wrapped(..)) => {}
+        e => panic!("{:?}", e.map(|_| ())),
+    }
+}
+
+#[test]
+fn unsolved_implicit_argument() {
+    let code = format!(
+        "{}definition const : {{A : Type}} -> Nat -> Nat;\
+        clause const n = n;\
+        definition use : Nat;\
+        clause use = const Zero;",
+        NAT
+    );
+    let err = check_code(&code).unwrap_err();
+    match &err {
+        TCE::MetaUnsolved(_, info) => {
+            assert_eq!(info.ty, Term::universe(Level::default()));
+            assert!(info.tele.is_empty());
+        }
+        _ => panic!(),
+    }
+    let msg = err.to_string();
+    assert!(msg.starts_with("Cannot infer implicit argument `A` of `const` at line"));
+}
+
+#[test]
+fn explicit_universe_levels() {
+    let wrap = |level: &str| {
+        format!(
+            "data Wrap : {} {{ constructor wrap Type; }};\
+            definition unwrap : Wrap -> Type;\
+            clause unwrap (wrap A) = A;\
+            definition id1 : (A : Type1) -> A -> A;\
+            clause id1 A a = a;\
+            definition nat : Type;\
+            clause nat = id1 Type (unwrap (wrap Nat));",
+            level
+        )
+    };
+    check_code(&format!("{}{}", NAT, wrap("Type1"))).unwrap();
+    match check_code(&format!("{}{}", NAT, wrap("Type0"))) {
+        Err(TCE::DifferentLevel(..)) => {}
+        _ => panic!(),
+    }
+    // Parameters don't count, `A : Type1` but `List A : Type0`.
+    let code = "data List (A : Type) { constructor nil; constructor cons (x : A) (xs : List A); };\
+        definition nats : Type0;\
+        clause nats = List Nat;";
+    check_code(&format!("{}{}", NAT, code)).unwrap();
+    // An identity type is in the universe of the type of its sides.
+    let code = |level: &str| {
+        format!(
+            "definition eq : (Type0 -> Type0) -> {};\
+            clause eq f = (f = f);",
+            level
+        )
+    };
+    check_code(&code("Type1")).unwrap();
+    match check_code(&code("Type0")) {
+        Err(TCE::DifferentLevel(..)) => {}
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn universe_polymorphism() {
+    let code = "data List (l : Level) (A : Type l) : Type l {\
+          constructor nil;\
+          constructor cons (x : A) (xs : List l A);\
+        };\
+        definition id : {l : Level} -> {A : Type l} -> A -> A;\
+        clause id a = a;\
+        definition zero : Nat;\
+        clause zero = id Zero;\
+        definition nat : Type1;\
+        clause nat = id Nat;\
+        definition nats : (l : Level) -> List (lsuc l) Nat;\
+        clause nats l = cons (id Zero) nil;";
+    check_code(&format!("{}{}", NAT, code)).unwrap();
+    let code = "definition bad : (l : Level) -> Type l;\
+        clause bad l = Type l;";
+    match check_code(code) {
+        Err(TCE::Wrapped(e, _)) => assert!(matches!(*e, TCE::DifferentTerm(..))),
+        _ => panic!(),
+    }
+}
+
+const VEC: &str = "data Vec (A : Type) : Nat -> Type {\
+      constructor vnil : Vec A Zero;\
+      constructor vcons {n : Nat} (x : A) (xs : Vec A n) : Vec A (Succ n);\
+    };";
+
+#[test]
+fn indexed_family() {
+    let code = "definition head : (A : Type) -> (n : Nat) -> Vec A (Succ n) -> A;\
+        clause head A n (vcons x xs) = x;\
+        definition empty : (v : Vec Nat Zero) -> v = vnil;\
+        clause empty vnil = refl;\
+        definition zero : head Nat Zero (vcons Zero vnil) = Zero;\
+        clause zero = refl;";
+    let tcs = check_code(&format!("{}{}{}", NAT, VEC, code)).unwrap();
+    match tcs.def(GI(3)) {
+        Decl::Data(info) => assert_eq!(info.indices.len(), 1),
+        _ => panic!(),
+    }
+    // Vectors of any length may be empty.
+    let code = "definition head : (A : Type) -> (n : Nat) -> Vec A n -> A;\
+        clause head A n (vcons x xs) = x;";
+    match check_code(&format!("{}{}{}", NAT, VEC, code)) {
+        Err(TCE::MissingClauses(name, cases)) => {
+            assert_eq!(name.text, "head");
+            assert_eq!(cases.len(), 1);
+        }
+        _ => panic!(),
+    }
+    // The forced indices are printed with the names of the variables.
+    let code = "definition f : (A : Type) -> (n : Nat) -> Vec A n -> Vec A n -> Nat;\
+        clause f A n vnil ys = Zero;";
+    match check_code(&format!("{}{}{}", NAT, VEC, code)) {
+        Err(TCE::MissingClauses(_, cases)) => {
+            assert_eq!(cases, vec![vec!["_", "|_(Succ n)_|", "(vcons _ _ _)", "_"]])
+        }
+        _ => panic!(),
+    }
+    let code = "data Bad (A : Type) : Nat -> Type { constructor bad : Vec A Zero; };";
+    match check_code(&format!("{}{}{}", NAT, VEC, code)) {
+        Err(TCE::ConsReturn(cons, Some(..))) => assert_eq!(cons.text, "bad"),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn absurd_not_empty() {
+    let code = "definition oops : (n : Nat) -> Vec Nat (Succ n) -> Nat;\
+        clause oops n ();";
+    match check_code(&format!("{}{}{}", NAT, VEC, code)) {
+        Err(TCE::NotEmpty(ty, _)) => assert_eq!(ty, "(Vec Nat (Succ n))"),
+        _ => panic!(),
+    }
+    // The unifier is stuck on the equation.
+    let code = "definition pred : Nat -> Nat;\
+        clause pred Zero = Zero;\
+        clause pred (Succ n) = n;\
+        definition oops : (n : Nat) -> pred n = Zero -> Nat;\
+        clause oops n ();";
+    match check_code(&format!("{}{}", NAT, code)) {
+        Err(TCE::NotEmpty(ty, _)) => assert_eq!(ty, "((pred n) = Zero)"),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn meta_signature() {
+    let code = "definition id : {A : Type} -> A -> A;\
+        clause id a = a;\
+        definition id2 : {A : _t} -> A -> A;\
+        clause id2 a = id a;\
+        clause zero = id2 Zero;\
+        definition succ : _n -> Nat;\
+        clause succ n = Succ n;";
+    let tcs = check_code(&format!("{}{}", NAT, code)).unwrap();
+    let signature = |ix| match tcs.def(GI(ix)) {
+        Decl::Func(f) => f.signature.clone(),
+        _ => panic!(),
+    };
+    // Inferred types are evaluated.
+    let nat = Term::Whnf(Val::inductive(GI(0), vec![]));
+    assert_eq!(signature(7), nat);
+    let (tele, _) = signature(9).tele_view();
+    assert_eq!(tele[0].ty, nat);
+    match check_code("definition oops : _t;") {
+        Err(TCE::MetaUnsolved(_, info)) => {
+            assert_eq!(info.origin, MetaOrigin::User("t".to_owned()))
+        }
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn pattern_signature() {
+    let code = "clause succ n = Succ n;\
+        clause two = succ (succ Zero);";
+    let tcs = check_code(&format!("{}{}", NAT, code)).unwrap();
+    let nat = Term::Whnf(Val::inductive(GI(0), vec![]));
+    match tcs.def(GI(3)) {
+        Decl::Func(f) => {
+            let (tele, ret) = f.signature.clone().tele_view();
+            assert_eq!(tele.len(), 1);
+            assert_eq!(tele[0].ty, nat);
+            assert_eq!(ret, nat);
+        }
+        _ => panic!(),
+    }
+    match check_code(&format!("{}clause zero n = Zero;", NAT)) {
+        Err(TCE::MetaUnsolved(_, info)) => assert_eq!(
+            info.origin,
+            MetaOrigin::ParamType(Some("n".to_owned()), "zero".to_owned())
+        ),
+        _ => panic!(),
+    }
+    let code = "definition zero : Nat;\
+        clause zero n = Zero;";
+    match check_code(&format!("{}{}", NAT, code)) {
+        Err(TCE::CantElim(ty, _)) => assert_eq!(ty, "Nat"),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn implicit_pattern() {
+    let code = "definition const : {A B : Type} -> A -> B -> A;\
+        clause const {B = X} a b = a;\
+        definition pred : {n : Nat} -> n = n -> Nat;\
+        clause pred {Zero} refl = Zero;\
+        clause pred {(Succ n)} refl = n;";
+    let tcs = check_code(&format!("{}{}", NAT, code)).unwrap();
+    match tcs.def(GI(5)) {
+        Decl::Func(f) => assert_eq!(f.clauses.len(), 2),
+        _ => panic!(),
+    }
+    let code = "definition const : {A B : Type} -> A -> B -> A;\
+        clause const {C = X} a b = a;";
+    match check_code(code) {
+        Err(TCE::UnexpectedImplicit(Some(name))) => assert_eq!(name.text, "C"),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn implicit_argument() {
+    let code = "definition zero : {n : Nat} -> Nat;\
+        clause zero = Zero;\
+        definition const : {A B : Type} -> A -> B -> A;\
+        clause const a b = a;\
+        definition one : zero {Succ Zero} = const {B = Nat} Zero (Succ Zero);\
+        clause one = refl;";
+    check_code(&format!("{}{}", NAT, code)).unwrap();
+    // Trailing implicit arguments, unless an implicit function is expected.
+    let code = "data List (A : Type) { constructor nil; constructor cons (x : A) (xs : List A); };\
+        definition nats : List Nat;\
+        clause nats = nil;\
+        definition apply : ({A : Type} -> A -> A) -> Nat;\
+        clause apply f = f Zero;\
+        definition id : {A : Type} -> A -> A;\
+        clause id a = a;\
+        definition zero : Nat;\
+        clause zero = apply id;";
+    check_code(&format!("{}{}", NAT, code)).unwrap();
+    let code = "definition id : {A : Type} -> A -> A;\
+        clause id a = a;\
+        definition zero : Nat;\
+        clause zero = id Zero {Nat};";
+    match check_code(&format!("{}{}", NAT, code)) {
+        Err(TCE::NotPi(..)) => {}
+        _ => panic!(),
+    }
+    let code = "definition const : {A B : Type} -> A -> B -> A;\
+        clause const a b = a;\
+        definition zero : Nat;\
+        clause zero = const {C = Nat} Zero Zero;";
+    match check_code(&format!("{}{}", NAT, code)) {
+        Err(TCE::UnexpectedImplicit(Some(name))) => assert_eq!(name.text, "C"),
+        _ => panic!(),
+    }
+}
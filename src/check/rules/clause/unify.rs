@@ -0,0 +1,287 @@
+use std::{collections::BTreeSet, rc::Rc};
+
+use voile_util::{
+    level::Level,
+    uid::{DBI, GI},
+};
+
+use crate::{
+    check::{
+        monad::{TCE, TCM, TCS},
+        pats::PatSubst,
+        rules::{
+            clause::split::{cons_tele, data_args},
+            simplify_ref,
+        },
+    },
+    syntax::{
+        core::{
+            subst::{DeBruijn, RedEx, Subst},
+            Closure, Decl, Elim, Tele, TeleS, Term, Val,
+        },
+        pat::Pat,
+    },
+};
+
+/// An equation $u = v : A$ to be unified, in the context of the telescope
+/// being unified.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.Unify.html#UnifyState).
+#[derive(Debug, Clone)]
+pub(super) struct UnifyEq {
+    pub(super) lhs: Term,
+    pub(super) rhs: Term,
+    pub(super) ty: Term,
+}
+
+impl UnifyEq {
+    pub(super) fn new(lhs: Term, rhs: Term, ty: Term) -> Self {
+        Self { lhs, rhs, ty }
+    }
+}
+
+impl RedEx for UnifyEq {
+    fn reduce_dbi(self, subst: Rc<Subst>) -> Self {
+        let lhs = self.lhs.reduce_dbi(subst.clone());
+        let rhs = self.rhs.reduce_dbi(subst.clone());
+        let ty = self.ty.reduce_dbi(subst);
+        Self { lhs, rhs, ty }
+    }
+}
+
+/// Result of unification.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.Unify.html#UnificationResult%27).
+pub(super) enum Unification {
+    /// The equations are solved.
+    /// $\Gamma'$ is the new telescope, $\Gamma' \vdash \sigma : \Gamma$.
+    Yes(Tele, Rc<PatSubst>),
+    /// The equations can never be solved (conflict or cycle).
+    No(TCE),
+}
+
+/// How a variable occurs in a term.
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone)]
+enum Occurrence {
+    /// Does not occur.
+    No,
+    /// Occurs under a function call, a meta or an elimination,
+    /// which may be gone after reduction.
+    Flexible,
+    /// Occurs under constructors only.
+    Rigid,
+}
+
+/// Visit the free variables of `term`, with whether the occurrence
+/// is rigid or not.
+fn visit_vars(term: &Term, depth: usize, rigid: bool, f: &mut impl FnMut(DBI, bool)) {
+    let visit_elims = |elims: &[Elim], f: &mut _| {
+        for elim in elims {
+            if let Elim::App(a) = elim {
+                visit_vars(a, depth, false, f);
+            }
+        }
+    };
+    let val = match term {
+        Term::Whnf(val) => val,
+        Term::Redex(_, _, elims) => return visit_elims(elims, f),
+    };
+    match val {
+        Val::Var(DBI(i), elims) => {
+            if *i >= depth {
+                f(DBI(i - depth), rigid && elims.is_empty());
+            }
+            visit_elims(elims, f)
+        }
+        Val::Cons(_, args) => args.iter().for_each(|a| visit_vars(a, depth, rigid, f)),
+        Val::Meta(_, elims) => visit_elims(elims, f),
+        Val::Data(info) => (info.args.iter()).for_each(|a| visit_vars(a, depth, false, f)),
+        Val::Pi(bind, Closure::Plain(body)) | Val::Lam(bind, Closure::Plain(body)) => {
+            visit_vars(&bind.ty, depth, false, f);
+            visit_vars(body, depth + 1, false, f);
+        }
+        Val::Id(ty, a, b) => {
+            visit_vars(ty, depth, false, f);
+            visit_vars(a, depth, false, f);
+            visit_vars(b, depth, false, f);
+        }
+        Val::Type(l) | Val::Level(l) => l.atoms().for_each(|a| visit_vars(a, depth, false, f)),
+        Val::LevelType | Val::Axiom(..) | Val::Refl => {}
+    }
+}
+
+fn free_vars(term: &Term) -> BTreeSet<usize> {
+    let mut vars = BTreeSet::new();
+    visit_vars(term, 0, true, &mut |DBI(i), _| {
+        vars.insert(i);
+    });
+    vars
+}
+
+fn occurrence(x: DBI, term: &Term) -> Occurrence {
+    let mut occ = Occurrence::No;
+    visit_vars(term, 0, true, &mut |i, rigid| {
+        if i == x {
+            let this = if rigid {
+                Occurrence::Rigid
+            } else {
+                Occurrence::Flexible
+            };
+            occ = occ.max(this);
+        }
+    });
+    occ
+}
+
+/// The solution rule, $x := u$.
+/// Variables that $u$ depends on are moved in front of $x$, and $x$ is
+/// removed from the telescope. Returns $\Gamma'$ and
+/// $\Gamma' \vdash \sigma : \Gamma$, or `None` if $u$ depends on $x$.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Telescope.html#instantiateTelescope).
+fn solution(tele: &TeleS, DBI(x): DBI, u: &Term) -> Option<(Tele, Rc<PatSubst>)> {
+    let len = tele.len();
+    // Positions are from left to right.
+    let pos_x = len - x - 1;
+    let mut need = BTreeSet::new();
+    let mut todo = (free_vars(u).into_iter())
+        .map(|i| len - i - 1)
+        .collect::<Vec<_>>();
+    while let Some(pos) = todo.pop() {
+        if need.insert(pos) {
+            todo.extend(free_vars(&tele[pos].ty).into_iter().map(|i| pos - i - 1));
+        }
+    }
+    if need.contains(&pos_x) {
+        return None;
+    }
+    let rest = (0..len).filter(|pos| *pos != pos_x && !need.contains(pos));
+    let order = need.iter().copied().chain(rest).collect::<Vec<_>>();
+    let mut new_pos = vec![len; len];
+    for (new, &old) in order.iter().enumerate() {
+        new_pos[old] = new;
+    }
+    // Used for the variables that are not in scope, which are never referred:
+    // `need` is closed under the dependencies and the other binders keep their
+    // order, so a binder only refers to the binders put before it. The ones
+    // in `need` don't refer to $x$ either, or $x$ would be in `need`.
+    let dummy = || Term::universe(Level::default());
+    // From the first `old_len` old binders to the first `new_len` new binders.
+    let rename = |old_len: usize, new_len: usize, x_val: Term| {
+        Subst::parallel((0..old_len).rev().map(|pos| match pos {
+            pos if pos == pos_x => x_val.clone(),
+            pos => match new_len.checked_sub(new_pos[pos] + 1) {
+                Some(i) => Term::from_dbi(DBI(i)),
+                None => dummy(),
+            },
+        }))
+    };
+    let u_at = |new_len: usize| {
+        if new_len >= need.len() {
+            u.clone().reduce_dbi(rename(len, new_len, dummy()))
+        } else {
+            dummy()
+        }
+    };
+    let new_tele = (order.iter().enumerate())
+        .map(|(new, &old)| {
+            let bind = tele[old].clone();
+            bind.map_term(|ty| ty.reduce_dbi(rename(old, new, u_at(new))))
+        })
+        .collect::<Tele>();
+    let new_len = len - 1;
+    let x_val = Pat::Forced(u_at(new_len));
+    let sigma = PatSubst::parallel((0..len).rev().map(|pos| match pos {
+        pos if pos == pos_x => x_val.clone(),
+        pos => Pat::Var(DBI(new_len - new_pos[pos] - 1)),
+    }));
+    Some((new_tele, sigma))
+}
+
+fn whnf(tcs: &TCS, term: Term) -> TCM<Term> {
+    match simplify_ref(tcs, term) {
+        Ok(val) => Ok(Term::Whnf(val)),
+        Err(TCE::Blocked(blocked)) => Ok(blocked.anyway),
+        Err(e) => Err(e),
+    }
+}
+
+/// Types of the constructor arguments `args`, where `ty` is the type of
+/// the constructor application.
+fn cons_arg_tys(tcs: &TCS, ty: Term, cons_ix: GI, args: &[Term]) -> TCM<Vec<Term>> {
+    let data = match simplify_ref(tcs, ty)? {
+        Val::Data(data) => data,
+        e => return Err(TCE::not_data(e)),
+    };
+    let cons = match tcs.def(cons_ix) {
+        Decl::Cons(c) => c,
+        _ => unreachable!(),
+    };
+    let (params, _) = data_args(tcs, data.def, data.args);
+    let theta = cons_tele(&cons.params, params);
+    let tys = (theta.into_iter().map(|bind| bind.ty).enumerate())
+        .map(|(i, ty)| ty.reduce_dbi(Subst::parallel(args[..i].iter().rev().cloned())))
+        .collect();
+    Ok(tys)
+}
+
+/// Unify the equations `eqs` in the context `tele`, using the
+/// deletion, solution, injectivity, conflict and cycle rules from
+/// Cockx's paper.
+///
+/// Returns `Err` with [`UnifyStuck`](crate::check::monad::TCE::UnifyStuck)
+/// if none of the rules apply.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.LHS.Unify.html#unifyIndices).
+pub(super) fn unify(tcs: &TCS, mut tele: Tele, mut eqs: Vec<UnifyEq>) -> TCM<Unification> {
+    let mut sigma = Rc::<PatSubst>::default();
+    while !eqs.is_empty() {
+        let eq = eqs.remove(0);
+        let lhs = whnf(tcs, eq.lhs.clone())?;
+        let rhs = whnf(tcs, eq.rhs.clone())?;
+        // Deletion, which implies K, so we only delete reflexive
+        // constructor equations via injectivity without K.
+        if lhs == rhs {
+            if !tcs.without_k {
+                continue;
+            }
+            if !matches!(lhs, Term::Whnf(Val::Cons(..))) {
+                let (a, b) = (tcs.pretty(&tele, &eq.lhs), tcs.pretty(&tele, &eq.rhs));
+                let ty = tcs.pretty(&tele, &eq.ty);
+                return Err(TCE::UnifyReflexive(a, b, ty, tcs.current_loc));
+            }
+        }
+        if let (Term::Whnf(Val::Cons(c0, a0)), Term::Whnf(Val::Cons(c1, a1))) = (&lhs, &rhs) {
+            // Conflict
+            if c0.cons_ix != c1.cons_ix {
+                return Ok(Unification::No(TCE::unify_conflict(lhs, rhs)));
+            }
+            // Injectivity
+            let tys = cons_arg_tys(tcs, eq.ty, c0.cons_ix, a0)?;
+            let args = a0.iter().cloned().zip(a1.iter().cloned()).zip(tys);
+            let new_eqs = args.map(|((a, b), ty)| UnifyEq::new(a, b, ty));
+            eqs.splice(0..0, new_eqs);
+            continue;
+        }
+        let mut solved = None;
+        for &(var, term) in &[(&lhs, &rhs), (&rhs, &lhs)] {
+            let x = match var.dbi_view() {
+                Some(x) => x,
+                None => continue,
+            };
+            // Cycle
+            if occurrence(x, term) == Occurrence::Rigid {
+                return Ok(Unification::No(TCE::unify_cycle(lhs, rhs)));
+            }
+            // Solution
+            solved = solution(&tele, x, term);
+            if solved.is_some() {
+                break;
+            }
+        }
+        let (new_tele, rho) = match solved {
+            Some(solved) => solved,
+            None => return Err(TCE::unify_stuck(lhs, rhs)),
+        };
+        tele = new_tele;
+        eqs = eqs.reduce_dbi(rho.to_term_subst());
+        sigma = rho.compose(sigma);
+    }
+    Ok(Unification::Yes(tele, sigma))
+}
@@ -1,18 +1,37 @@
+use voile_util::{
+    loc::{Ident, ToLoc},
+    tags::Plicit,
+    uid::{next_uid, DBI, GI},
+};
+
 use crate::{
     check::{
-        monad::{TCM, TCS},
-        rules::term::check,
+        monad::{TCE, TCM, TCS},
+        rules::{
+            positivity::{occurs, positive, Target},
+            term::{check, simplify},
+        },
     },
     syntax::{
-        abs::{AbsConsInfo, AbsDataInfo, AbsTele},
-        core::{ConsInfo, DataInfo, Decl, Val},
+        abs::{Abs, AbsCodataInfo, AbsConsInfo, AbsDataInfo, AbsProjInfo, AbsTele},
+        common::{ConHead, Ductive},
+        core::{
+            subst::{DeBruijn, RedEx, Subst},
+            Bind, CodataInfo, ConsInfo, DataInfo, Decl, Elim, FoldVal, Level, ProjInfo, Tele, Term,
+            Val, TYPE_OMEGA,
+        },
     },
 };
 
 /// The checked tele is put into the returned `tcs.gamma`.
+/// The universe `ty` is weakened by the previous bindings.
 pub fn check_tele(mut tcs: TCS, tele: AbsTele, ty: &Val) -> TCM {
-    for bind in tele {
-        let (checked, new_tcs) = check(tcs, &bind.ty, ty)?;
+    for (i, bind) in tele.into_iter().enumerate() {
+        let ty = match ty {
+            Val::Type(level) => Val::Type(level.clone().reduce_dbi(Subst::raise(DBI(i)))),
+            ty => ty.clone(),
+        };
+        let (checked, new_tcs) = check(tcs, &bind.ty, &ty)?;
         tcs = new_tcs;
         let bind = bind.map_term(|_| checked.ast);
         tcs.gamma.push(bind);
@@ -20,30 +39,120 @@ pub fn check_tele(mut tcs: TCS, tele: AbsTele, ty: &Val) -> TCM {
     Ok(tcs)
 }
 
+/// Like Agda, the parameters are not constrained by the universe of the
+/// datatype, only the constructor arguments are. Otherwise `List (A : Type)`
+/// would have to live in `Type1`.
+fn check_params(tcs: TCS, tele: AbsTele) -> TCM {
+    check_tele(tcs, tele, &TYPE_OMEGA)
+}
+
+/// The universe of a record type, in the context of its parameters.
+fn check_universe(tcs: TCS, universe: Option<Abs>) -> TCM<(TCS, Level)> {
+    let universe = match universe {
+        Some(universe) => universe,
+        None => return Ok((tcs, Level::default())),
+    };
+    let (checked, tcs) = check(tcs, &universe, &TYPE_OMEGA)?;
+    let (ty, tcs) = simplify(tcs, checked.ast)?;
+    match ty {
+        Val::Type(level) => Ok((tcs, level)),
+        ty => Err(TCE::not_universe(ty).wrap(universe.loc())),
+    }
+}
+
+/// The indices and the universe of a datatype, in the context of its parameters.
+fn check_data_type(tcs: TCS, data: &Ident, ty: Option<Abs>) -> TCM<(TCS, Tele, Level)> {
+    let ty = match ty {
+        Some(ty) => ty,
+        None => return Ok((tcs, vec![], Level::default())),
+    };
+    let (checked, tcs) = check(tcs, &ty, &TYPE_OMEGA)?;
+    let (indices, universe) = checked.ast.tele_view();
+    let (universe, tcs) = simplify(tcs, universe)?;
+    let level = match universe {
+        Val::Type(level) => level.normalize(),
+        universe => return Err(TCE::not_universe(universe).wrap(ty.loc())),
+    };
+    // The level is in the context of the indices, move it out.
+    let index_len = indices.len();
+    let dummies = (0..index_len).map(|_| Term::fresh_axiom());
+    let lowered = level.clone().reduce_dbi(Subst::parallel(dummies));
+    if lowered.clone().reduce_dbi(Subst::raise(DBI(index_len))) != level {
+        return Err(TCE::DataSortIndexed(data.clone()));
+    }
+    Ok((tcs, indices, lowered))
+}
+
+/// The indices of the datatype a constructor returns,
+/// in the context of the data parameters and the constructor parameters.
+fn check_cons_return(tcs: TCS, cons: &Ident, data: GI, ret: Option<Abs>) -> TCM<(TCS, Vec<Term>)> {
+    let (param_len, index_len) = match tcs.def(data) {
+        Decl::Data(info) => (info.params.len(), info.indices.len()),
+        _ => unreachable!(),
+    };
+    let ret = match ret {
+        Some(ret) => ret,
+        None if index_len == 0 => return Ok((tcs, vec![])),
+        None => return Err(TCE::cons_return(cons.clone(), None)),
+    };
+    let (checked, tcs) = check(tcs, &ret, &TYPE_OMEGA)?;
+    let (ret_ty, tcs) = simplify(tcs, checked.ast.clone())?;
+    let mut args = match ret_ty {
+        Val::Data(info) if info.def == data && info.args.len() == param_len + index_len => {
+            info.args
+        }
+        _ => return Err(TCE::cons_return(cons.clone(), Some(checked.ast))),
+    };
+    let indices = args.split_off(param_len);
+    // The parameters are the outermost variables.
+    let gamma_len = tcs.gamma.len();
+    let params = (0..param_len).map(|i| Term::from_dbi(DBI(gamma_len - 1 - i)));
+    if !args.into_iter().eq(params) {
+        return Err(TCE::cons_return(cons.clone(), Some(checked.ast)));
+    }
+    Ok((tcs, indices))
+}
+
 fn check_cons(tcs: TCS, cons: AbsConsInfo, ty: &Val) -> TCM<(TCS, ConsInfo)> {
     let param_len = tcs.gamma.len();
-    let mut tcs = check_tele(tcs, cons.tele, ty)?;
+    let locs = cons
+        .tele
+        .iter()
+        .map(|bind| bind.ty.loc())
+        .collect::<Vec<_>>();
+    let tcs = check_tele(tcs, cons.tele, ty)?;
+    let (mut tcs, indices) = check_cons_return(tcs, &cons.name, cons.data_ix, cons.ret)?;
     let info = ConsInfo {
         loc: cons.source,
         name: cons.name,
         params: tcs.gamma.split_off(param_len),
         data: cons.data_ix,
+        indices,
         // Inductive!
         fields: None,
     };
+    let target = Target::Data(info.data);
+    for (bind, loc) in info.params.iter().zip(locs) {
+        if let Err(occ) = positive(&tcs, target, &bind.ty, 0, &mut vec![]) {
+            let name = tcs.def(info.data).def_name().clone();
+            return Err(TCE::not_positive(name, occ, loc));
+        }
+    }
     Ok((tcs, info))
 }
 
 pub fn check_data(tcs: TCS, data: AbsDataInfo, conses: Vec<AbsConsInfo>) -> TCM {
-    let t = Val::Type(data.level);
-    let mut tcs = check_tele(tcs, data.tele, &t)?;
+    let tcs = check_params(tcs, data.tele)?;
+    let (mut tcs, indices, level) = check_data_type(tcs, &data.name, data.ty)?;
+    let t = Val::Type(level.clone());
     let param_len = tcs.gamma.len();
 
     let info = DataInfo {
         params: tcs.gamma.clone(),
         loc: data.source,
         name: data.name,
-        level: data.level,
+        indices,
+        level,
         conses: data.conses,
     };
     tcs.sigma.push(Decl::Data(info));
@@ -65,3 +174,127 @@ pub fn check_data(tcs: TCS, data: AbsDataInfo, conses: Vec<AbsConsInfo>) -> TCM
     tcs.gamma.clear();
     Ok(tcs)
 }
+
+fn check_proj(tcs: TCS, proj: AbsProjInfo, ty: &Val) -> TCM<(TCS, ProjInfo)> {
+    let (checked, tcs) = check(tcs, &proj.ty, ty)?;
+    let info = ProjInfo {
+        loc: proj.source,
+        name: proj.name,
+        codata: proj.codata_ix,
+        ty: checked.ast,
+    };
+    Ok((tcs, info))
+}
+
+/// The projection types are checked in the context of the codata parameters
+/// and the record value `self`.
+pub fn check_codata(
+    tcs: TCS,
+    codata: AbsCodataInfo,
+    projs: Vec<AbsProjInfo>,
+    cons: Option<AbsConsInfo>,
+) -> TCM {
+    let tcs = check_params(tcs, codata.tele)?;
+    let (mut tcs, level) = check_universe(tcs, codata.universe)?;
+    let t = Val::Type(level.clone());
+    let codata_ix = tcs.sigma.len();
+    let (self_ref, self_uid) = match codata.self_ref {
+        Some((ident, uid)) => (Some(ident), uid),
+        None => (None, unsafe { next_uid() }),
+    };
+
+    let fields = (projs.iter().zip(&codata.fields))
+        .map(|(proj, ix)| (proj.name.text.clone(), *ix))
+        .collect();
+    let info = CodataInfo {
+        params: tcs.gamma.clone(),
+        loc: codata.source,
+        self_ref,
+        name: codata.name.clone(),
+        fields,
+        level,
+        // Decided after the projections are checked.
+        eta: false,
+    };
+    tcs.sigma.push(Decl::Codata(info));
+
+    let params_len = tcs.gamma.len();
+    let params = (0..params_len).rev().map(DBI).map(Elim::from_dbi).collect();
+    let me = Term::def(GI(codata_ix), codata.name, params);
+    tcs.gamma.push(Bind::new(Plicit::Ex, self_uid, me));
+
+    // Recursive codata have no eta-equality, otherwise the conversion
+    // check may not terminate.
+    let mut eta = true;
+
+    for proj in projs {
+        let (new_tcs, proj) = check_proj(tcs, proj, &t)?;
+        tcs = new_tcs;
+        debug_assert_eq!(params_len + 1, tcs.gamma.len());
+
+        eta &= !occurs(Target::Data(GI(codata_ix)), &proj.ty, 0);
+        tcs.sigma.push(Decl::Proj(proj));
+    }
+    if let Decl::Codata(info) = &mut tcs.sigma[codata_ix] {
+        info.eta = eta;
+    }
+    tcs.gamma.clear();
+    match cons {
+        Some(cons) => check_record_cons(tcs, cons),
+        None => Ok(tcs),
+    }
+}
+
+/// The parameters of a record constructor are the fields, typed by the
+/// projection types where `self` is the constructor applied to the fields
+/// before, so the type of a field can only depend on the fields before it.
+fn check_record_cons(mut tcs: TCS, cons: AbsConsInfo) -> TCM {
+    let cons_ix = GI(tcs.sigma.len());
+    let mut fields = match tcs.def(cons.data_ix) {
+        Decl::Codata(info) => (info.fields.iter())
+            .map(|(name, ix)| (*ix, name.clone()))
+            .collect::<Vec<_>>(),
+        _ => unreachable!(),
+    };
+    fields.sort();
+    let names = fields
+        .iter()
+        .map(|(_, name)| name.clone())
+        .collect::<Vec<_>>();
+    let head = ConHead::new(cons.name.clone(), cons_ix, Ductive::In, names.clone());
+    // Standing for the fields not bound yet.
+    let later = (fields.iter())
+        .map(|_| unsafe { next_uid() })
+        .collect::<Vec<_>>();
+    let mut params = Vec::with_capacity(fields.len());
+    for (i, (ix, _)) in fields.into_iter().enumerate() {
+        let proj = match tcs.def(ix) {
+            Decl::Proj(proj) => proj,
+            _ => unreachable!(),
+        };
+        let args = ((0..i).rev().map(|j| Term::from_dbi(DBI(j))))
+            .chain(later[i..].iter().map(|uid| Term::postulate(*uid)))
+            .collect();
+        let me = Term::cons(head.clone(), args);
+        let ty = (proj.ty.clone()).reduce_dbi(Subst::raise(DBI(i)).cons(me));
+        let later = &later[i..];
+        let mentions_later = ty.try_fold_val((), |(), val| match val {
+            Val::Axiom(uid) if later.contains(uid) => Err(()),
+            _ => Ok(()),
+        });
+        if mentions_later.is_err() {
+            return Err(TCE::NotRecordCons(cons.name, proj.name.clone()));
+        }
+        params.push(Bind::new(Plicit::Ex, unsafe { next_uid() }, ty));
+    }
+    let info = ConsInfo {
+        loc: cons.source,
+        name: cons.name,
+        params,
+        data: cons.data_ix,
+        indices: vec![],
+        fields: Some(names),
+    };
+    tcs.sigma.push(Decl::Cons(info));
+    Ok(tcs)
+}
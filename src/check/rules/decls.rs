@@ -4,9 +4,9 @@ use crate::{
     check::{
         monad::{TCM, TCS},
         rules::{
-            clause::clause,
-            data::check_data,
-            term::{check, HasMeta},
+            clause::{clause, coverage, guardedness, mutual_group, termination},
+            data::{check_codata, check_data},
+            term::{check, solve_level_bounds, HasMeta},
             ERROR_MSG,
         },
     },
@@ -20,6 +20,8 @@ pub fn check_decls(mut tcs: TCS, decls: Vec<AbsDecl>) -> TCM {
     let mut decls = decls.into_iter().map(Some).collect::<Vec<_>>();
     let range = 0..decls.len();
     let take = |decls: &mut [Option<AbsDecl>], i: usize| decls[i].take().expect(ERROR_MSG);
+    // Functions waiting for the functions they call to be termination-checked.
+    let mut unchecked = Vec::new();
 
     for i in range {
         tcs.enter_def(GI(i));
@@ -33,43 +35,131 @@ pub fn check_decls(mut tcs: TCS, decls: Vec<AbsDecl>) -> TCM {
                 let cs = (i.conses.iter())
                     .map(|GI(j)| match take(&mut decls, *j) {
                         AbsDecl::Cons(i) => i,
-                        _ => unreachable!(ERROR_MSG),
+                        _ => unreachable!("{}", ERROR_MSG),
                     })
                     .collect();
                 // TODO: Inline meta??
                 tcs = check_data(tcs, i, cs)?;
             }
-            AbsDecl::Cons(_) => unreachable!(ERROR_MSG),
-            AbsDecl::Defn(i) => {
-                let (ty, new_tcs) = check(tcs, &i.ty, &TYPE_OMEGA)?;
-                let (signature, new_tcs) = ty.ast.inline_meta(new_tcs)?;
+            AbsDecl::Cons(_) => unreachable!("{}", ERROR_MSG),
+            AbsDecl::Codata(i) => {
+                let ps = (i.fields.iter())
+                    .map(|GI(j)| match take(&mut decls, *j) {
+                        AbsDecl::Proj(i) => i,
+                        _ => unreachable!("{}", ERROR_MSG),
+                    })
+                    .collect();
+                let cons = i.cons.map(|GI(j)| match take(&mut decls, j) {
+                    AbsDecl::Cons(i) => i,
+                    _ => unreachable!("{}", ERROR_MSG),
+                });
+                tcs = check_codata(tcs, i, ps, cons)?;
+            }
+            AbsDecl::Proj(_) => unreachable!("{}", ERROR_MSG),
+            AbsDecl::Defn(defn) => {
+                let (ty, new_tcs) = check(tcs, &defn.ty, &TYPE_OMEGA)?;
+                let mut new_tcs = solve_level_bounds(new_tcs)?;
+                new_tcs.check_constraints()?;
+                // The clauses may solve the rest of the metas.
+                let (signature, new_tcs) =
+                    if new_tcs.meta_ctx().has_unsolved() && has_clauses(&decls[i + 1..], GI(i)) {
+                        (ty.ast, new_tcs)
+                    } else {
+                        ty.ast.inline_meta(new_tcs)?
+                    };
                 tcs = new_tcs;
                 let func = FuncInfo {
-                    loc: i.source,
-                    name: i.name,
+                    loc: defn.source,
+                    name: defn.name,
                     signature,
                     clauses: Vec::with_capacity(2),
+                    case_tree: None,
                 };
                 tcs.sigma.push(Decl::Func(func));
             }
-            AbsDecl::Clause(i) => {
-                let def_ix = i.definition;
+            AbsDecl::Clause(info) => {
+                let def_ix = info.definition;
                 let signature = match tcs.def(def_ix) {
                     Decl::Func(f) => f.signature.clone(),
                     _ => unreachable!(),
                 };
-                let (cls, new_tcs) = clause(tcs, i, signature)?;
+                // Share the metas with the signature.
+                let unsolved = tcs.meta_ctx[def_ix.0].has_unsolved();
+                if unsolved {
+                    tcs.reenter_def(def_ix);
+                }
+                let (cls, new_tcs) = clause(tcs, info, signature)?;
                 tcs = new_tcs;
                 match tcs.mut_def(def_ix) {
                     Decl::Func(f) => f.clauses.push(cls),
                     _ => unreachable!(),
                 };
                 tcs.sigma.push(Decl::ClausePlaceholder);
+                let is_last = !has_clauses(&decls[i + 1..], def_ix);
+                if unsolved && (is_last || !tcs.meta_ctx().has_unsolved()) {
+                    tcs = inline_func(tcs, def_ix)?;
+                }
+                // Check the coverage after the last clause is checked.
+                if is_last {
+                    let (tree, new_tcs) = coverage(tcs, def_ix)?;
+                    tcs = new_tcs;
+                    guardedness(&tcs, def_ix)?;
+                    match tcs.mut_def(def_ix) {
+                        Decl::Func(f) => f.case_tree = tree,
+                        _ => unreachable!(),
+                    };
+                    unchecked.push(def_ix);
+                    check_termination(&tcs, &mut unchecked, &decls[i + 1..])?;
+                }
             }
-            _ => unimplemented!(),
         }
+        tcs.check_constraints()?;
         tcs.exit_def();
         tcs.sanity_check();
     }
     Ok(tcs)
 }
+
+/// Are there clauses of the definition `def` in `decls`?
+fn has_clauses(decls: &[Option<AbsDecl>], def: GI) -> bool {
+    decls.iter().any(|decl| match decl {
+        Some(AbsDecl::Clause(c)) => c.definition == def,
+        _ => false,
+    })
+}
+
+/// Check the termination of the (mutually recursive) functions in `unchecked`,
+/// unless they call functions with clauses in `decls`, which are left to
+/// be checked together with those functions.
+fn check_termination(tcs: &TCS, unchecked: &mut Vec<GI>, decls: &[Option<AbsDecl>]) -> TCM<()> {
+    let mut next = 0;
+    while next < unchecked.len() {
+        match mutual_group(tcs, unchecked[next], unchecked, |f| !has_clauses(decls, f)) {
+            Some(group) => {
+                termination(tcs, &group)?;
+                unchecked.retain(|f| !group.contains(f));
+                next = 0;
+            }
+            None => next += 1,
+        }
+    }
+    Ok(())
+}
+
+/// Inline the metas in the signature and the clauses of the function `def`.
+fn inline_func(mut tcs: TCS, def: GI) -> TCM {
+    let (signature, clauses) = match tcs.mut_def(def) {
+        Decl::Func(f) => (f.signature.clone(), std::mem::take(&mut f.clauses)),
+        _ => unreachable!(),
+    };
+    let (signature, tcs) = signature.inline_meta(tcs)?;
+    let (clauses, mut tcs) = clauses.inline_meta(tcs)?;
+    match tcs.mut_def(def) {
+        Decl::Func(f) => {
+            f.signature = signature;
+            f.clauses = clauses;
+        }
+        _ => unreachable!(),
+    };
+    Ok(tcs)
+}
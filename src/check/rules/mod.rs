@@ -1,15 +1,17 @@
 pub use self::{
     decls::*,
-    term::{check, infer, simplify},
+    term::{check, infer, simplify, simplify_ref},
 };
 
 pub const ERROR_MSG: &str = "Please report this as a bug.";
 
 /// Type check a function clause.
 mod clause;
-/// Type check data type & constructor declarations.
+/// Type check (co)data type, constructor & projection declarations.
 mod data;
 /// Check a list of declarations.
 mod decls;
+/// Strict positivity checking for datatypes.
+mod positivity;
 /// Type check a term.
 mod term;
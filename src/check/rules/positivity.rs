@@ -0,0 +1,138 @@
+use voile_util::uid::{DBI, GI};
+
+use crate::{
+    check::monad::TCS,
+    syntax::core::{Closure, Decl, Elim, Term, Val},
+};
+
+/// What are we checking the occurrences of?
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(super) enum Target {
+    /// The datatype being defined.
+    Data(GI),
+    /// A datatype parameter, in the context of the constructor parameter.
+    Param(DBI),
+}
+
+impl Target {
+    fn is_var(self, DBI(x): DBI, depth: usize) -> bool {
+        match self {
+            Target::Param(DBI(i)) => x == i + depth,
+            Target::Data(..) => false,
+        }
+    }
+
+    fn is_def(self, gi: GI) -> bool {
+        self == Target::Data(gi)
+    }
+}
+
+fn occurs_elims(target: Target, elims: &[Elim], depth: usize) -> bool {
+    elims.iter().any(|elim| match elim {
+        Elim::App(a) => occurs(target, a, depth),
+        Elim::Proj(..) => false,
+    })
+}
+
+/// Does `target` occur in `term` at all?
+pub(super) fn occurs(target: Target, term: &Term, depth: usize) -> bool {
+    let val = match term {
+        Term::Whnf(val) => val,
+        Term::Redex(gi, _, elims) => {
+            return target.is_def(*gi) || occurs_elims(target, elims, depth);
+        }
+    };
+    match val {
+        Val::Var(x, elims) => target.is_var(*x, depth) || occurs_elims(target, elims, depth),
+        Val::Meta(_, elims) => occurs_elims(target, elims, depth),
+        Val::Data(info) => {
+            target.is_def(info.def) || info.args.iter().any(|a| occurs(target, a, depth))
+        }
+        Val::Cons(_, args) => args.iter().any(|a| occurs(target, a, depth)),
+        Val::Pi(bind, Closure::Plain(body)) | Val::Lam(bind, Closure::Plain(body)) => {
+            occurs(target, &bind.ty, depth) || occurs(target, body, depth + 1)
+        }
+        Val::Id(ty, a, b) => [ty, a, b].iter().any(|t| occurs(target, t, depth)),
+        Val::Type(l) | Val::Level(l) => l.atoms().any(|a| occurs(target, a, depth)),
+        Val::LevelType | Val::Axiom(..) | Val::Refl => false,
+    }
+}
+
+/// Check if `target` occurs only strictly positively in `term`,
+/// returns the non-positive occurrence if not.
+/// `visited` are the datatype parameters assumed to be positive.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Positivity.html#checkStrictlyPositive).
+pub(super) fn positive(
+    tcs: &TCS,
+    target: Target,
+    term: &Term,
+    depth: usize,
+    visited: &mut Vec<(GI, usize)>,
+) -> Result<(), Term> {
+    let non_positive = |term: &Term| Err(term.clone());
+    let (def, args) = match term {
+        Term::Redex(gi, _, elims) => match tcs.def(*gi) {
+            Decl::Data(..) => {
+                let args = elims.iter().filter_map(|elim| match elim {
+                    Elim::App(a) => Some(&**a),
+                    Elim::Proj(..) => None,
+                });
+                (*gi, args.collect::<Vec<_>>())
+            }
+            _ if occurs_elims(target, elims, depth) => return non_positive(term),
+            _ => return Ok(()),
+        },
+        Term::Whnf(Val::Data(info)) => (info.def, info.args.iter().collect()),
+        Term::Whnf(Val::Pi(bind, Closure::Plain(body))) => {
+            if occurs(target, &bind.ty, depth) {
+                return non_positive(term);
+            }
+            return positive(tcs, target, body, depth + 1, visited);
+        }
+        Term::Whnf(Val::Var(_, elims)) if occurs_elims(target, elims, depth) => {
+            return non_positive(term)
+        }
+        Term::Whnf(Val::Var(..)) => return Ok(()),
+        term if occurs(target, term, depth) => return non_positive(term),
+        _ => return Ok(()),
+    };
+    for (k, arg) in args.into_iter().enumerate() {
+        if !occurs(target, arg, depth) {
+            continue;
+        }
+        // The arguments of the datatype being defined are not allowed to
+        // mention itself, while other datatypes may use their parameters
+        // positively.
+        if target.is_def(def) || !param_positive(tcs, def, k, visited) {
+            return non_positive(term);
+        }
+        positive(tcs, target, arg, depth, visited)?;
+    }
+    Ok(())
+}
+
+/// Is the `k`-th parameter of the datatype `def` used strictly positively?
+fn param_positive(tcs: &TCS, def: GI, k: usize, visited: &mut Vec<(GI, usize)>) -> bool {
+    if visited.contains(&(def, k)) {
+        return true;
+    }
+    visited.push((def, k));
+    let info = match tcs.def(def) {
+        Decl::Data(info) => info,
+        _ => return false,
+    };
+    let params_len = info.params.len();
+    if k >= params_len {
+        return false;
+    }
+    info.conses.iter().all(|&cons_ix| {
+        let cons = match tcs.def(cons_ix) {
+            Decl::Cons(cons) => cons,
+            _ => unreachable!(),
+        };
+        (cons.params.iter().enumerate()).all(|(i, bind)| {
+            let target = Target::Param(DBI(params_len - k - 1 + i));
+            positive(tcs, target, &bind.ty, 0, visited).is_ok()
+        })
+    })
+}
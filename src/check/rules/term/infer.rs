@@ -3,18 +3,25 @@ use std::iter::once;
 use either::Either::{Left, Right};
 use voile_util::{
     loc::*,
+    meta::MI,
     tags::{Plicit, VarRec},
-    uid::{next_uid, DBI, GI},
+    uid::{next_uid, DBI, GI, UID},
 };
 
 use crate::{
     check::{
-        monad::{TCE, TCM, TCS},
-        rules::term::{check, whnf::simplify},
+        monad::{MetaOrigin, Problem, TCE, TCM, TCS},
+        rules::term::{
+            check, check_term, is_unsolved, postpone_problem, simplify_ref, term_level,
+            whnf::simplify,
+        },
     },
     syntax::{
         abs::Abs,
-        core::{subst::DeBruijn, Bind, CodataInfo, DataInfo, Decl, Elim, Term, TermInfo, Val},
+        core::{
+            subst::{DeBruijn, RedEx, Subst},
+            Bind, Closure, CodataInfo, DataInfo, Decl, Elim, Level, Term, TermInfo, Val,
+        },
     },
 };
 
@@ -40,85 +47,232 @@ pub fn infer(mut tcs: TCS, input_term: &Abs) -> InferTCM {
     Ok((evaluated, inferred_ty, tcs))
 }
 
+/// The origin of the meta inserted for the implicit parameter `param` of `fun`.
+fn implicit_arg(tcs: &TCS, param: UID, fun: &Abs) -> MetaOrigin {
+    let fun = match fun {
+        Abs::Def(id, ..) | Abs::Var(id, ..) | Abs::Cons(id, ..) | Abs::Proj(id, ..) => {
+            id.text.clone()
+        }
+        fun => fun.to_string(),
+    };
+    MetaOrigin::ImplicitArg(tcs.name_of(param).cloned(), fun)
+}
+
+/// Insert metas for the trailing implicit parameters of `expr` (whose type is
+/// `whnf`), like `nil` to `nil {_}`, since the data parameters of constructors
+/// are implicit. Nothing is inserted if an implicit function is expected.
+pub(super) fn insert_implicits(
+    mut tcs: TCS,
+    expr: &Abs,
+    mut evaluated: TermInfo,
+    mut whnf: Val,
+    expected_type: &Val,
+) -> TCM<(TermInfo, Val, TCS)> {
+    if matches!(expected_type, Val::Pi(bind, _) if bind.is_implicit()) {
+        return Ok((evaluated, whnf, tcs));
+    }
+    let fun = expr.clone().into_app_view().fun;
+    loop {
+        let (bind, clos) = match whnf {
+            Val::Pi(bind, clos) if bind.is_implicit() => (bind, clos),
+            _ => break Ok((evaluated, whnf, tcs)),
+        };
+        let origin = implicit_arg(&tcs, bind.name, &fun);
+        let meta = tcs.fresh_meta(*bind.ty, expr.loc(), origin);
+        evaluated = evaluated.map_ast(|t| t.apply(vec![meta.clone()]));
+        let (new_whnf, new_tcs) = simplify(tcs, clos.instantiate(meta))?;
+        whnf = new_whnf;
+        tcs = new_tcs;
+    }
+}
+
 fn infer_impl(tcs: TCS, abs: &Abs) -> InferTCM {
     let abs = match abs {
         Abs::Type(id, level) => {
-            let me = Term::universe(*level).at(id.loc);
-            return Ok((me, Term::universe(*level + 1), tcs));
+            let level = Level::from(*level);
+            let me = Term::universe(level.clone()).at(id.loc);
+            return Ok((me, Term::universe(level.lift(1)), tcs));
+        }
+        Abs::Universe(id, k, level) => {
+            let (level, tcs) = check(tcs, level, &Val::LevelType)?;
+            let level = term_level(&tcs, level.ast)?;
+            let level = match Level::from(*k) {
+                Level::Max(k, _) => level.lift(k),
+                Level::Omega => Level::Omega,
+            };
+            let me = Term::universe(level.clone()).at(id.loc + abs.loc());
+            return Ok((me, Term::universe(level.lift(1)), tcs));
+        }
+        Abs::Level(id) => {
+            let me = Term::Whnf(Val::LevelType).at(id.loc);
+            return Ok((me, Term::universe(Level::default()), tcs));
+        }
+        // A meta of a meta type.
+        Abs::Meta(id, _) => {
+            let mut tcs = tcs;
+            let origin = MetaOrigin::Universe(abs.to_string());
+            let level = tcs.fresh_meta(Term::Whnf(Val::LevelType), id.loc, origin);
+            let universe = Term::universe(Level::atom(level));
+            let origin = MetaOrigin::User(id.text.clone());
+            let ty = tcs.fresh_meta(universe, id.loc, origin.clone());
+            let me = tcs.fresh_meta(ty.clone(), id.loc, origin);
+            return Ok((me.at(id.loc), ty, tcs));
         }
         abs => abs.clone(),
     };
     let view = abs.into_app_view();
-    let (head, mut ty, mut tcs) = infer_head(tcs, &view.fun)?;
-    let mut elims = Vec::with_capacity(view.args.len());
-    for arg in view.args {
-        let (mut ty_val, mut new_tcs) = simplify(tcs, ty)?;
+    let (head, ty, tcs) = infer_head(tcs, &view.fun)?;
+    infer_elims(tcs, head, ty, &view.fun, view.args)
+}
+
+/// Infer the eliminations of `head` (of type `ty`) by `args`, where `fun` is
+/// the head expression. The rest of them are postponed once the type is
+/// blocked on a meta.
+pub(super) fn infer_elims(
+    mut tcs: TCS,
+    head: TermInfo,
+    mut ty: Term,
+    fun: &Abs,
+    args: Vec<Abs>,
+) -> InferTCM {
+    let mut elims = Vec::with_capacity(args.len());
+    for (done, arg) in args.iter().enumerate() {
         match loop {
+            let ty_val = match whnf_or_blocker(&tcs, ty.clone())? {
+                Ok(val) => val,
+                Err(mi) => {
+                    let head = head.map_ast(|t| t.apply_elim(elims));
+                    return postpone_elims(tcs, head, ty, fun, args[done..].to_vec(), mi);
+                }
+            };
             let (param, clos) = match ty_val {
                 Val::Pi(param, clos) => (param, clos),
                 Val::Data(i) if i.kind == VarRec::Record => break Right((i.def, i.args)),
                 e => return Err(TCE::NotPi(Term::Whnf(e), arg.loc())),
             };
-            let (param_ty, loop_tcs) = simplify(new_tcs, *param.ty)?;
-            new_tcs = loop_tcs;
             // In case this is an implicit argument
             if param.licit == Plicit::Im {
-                // This meta has type "param_ty".
-                let meta = new_tcs.fresh_meta();
+                // Given by the user
+                if matches!(arg, Abs::Implicit(name, _) if tcs.is_named(param.name, name)) {
+                    break Left((param, clos));
+                }
+                let origin = implicit_arg(&tcs, param.name, fun);
+                let meta = tcs.fresh_meta(*param.ty, fun.loc(), origin);
                 elims.push(Elim::app(meta.clone()));
-                let (new_ty_val, loop_tcs) = simplify(new_tcs, clos.instantiate(meta))?;
-                ty_val = new_ty_val;
-                new_tcs = loop_tcs;
+                ty = clos.instantiate(meta);
+            } else if let Abs::Implicit(name, _) = arg {
+                return Err(TCE::UnexpectedImplicit(name.clone()));
             } else {
-                break Left((param_ty, clos));
+                break Left((param, clos));
             }
         } {
             Left((param, clos)) => {
-                let (arg, new_tcs) = check(new_tcs, &arg, &param)?;
+                let arg = match arg {
+                    Abs::Implicit(_, arg) => &**arg,
+                    arg => arg,
+                };
+                let (arg, new_tcs) = check_term(tcs, arg, *param.ty)?;
                 ty = clos.instantiate(arg.ast.clone());
                 elims.push(Elim::app(arg.ast));
                 tcs = new_tcs;
             }
-            // FIXME: we should take this `_codata_elims` into account when inferring the
-            //  type of `proj_def` because in `type_of_decl` we're assuming no type params known
-            Right((codata_def, _codata_elims)) => match arg {
+            Right((codata_def, codata_args)) => match arg {
                 Abs::Proj(ident, proj_def) => {
-                    let (codata_name, codata_fields) = match new_tcs.def(codata_def) {
+                    let (codata_name, codata_fields) = match tcs.def(codata_def) {
                         Decl::Codata(i) => (i.name.clone(), &i.fields),
                         _ => unreachable!(),
                     };
-                    if !codata_fields.iter().any(|(_, &ix)| ix == proj_def) {
+                    if !codata_fields.iter().any(|(_, ix)| ix == proj_def) {
                         return Err(TCE::DifferentFieldCodata(
                             ident.loc,
                             codata_name.text.clone(),
-                            ident.text,
+                            ident.text.clone(),
                         ));
                     }
-                    elims.push(Elim::Proj(ident.text));
-                    ty = type_of_decl(&new_tcs, proj_def)?.ast;
-                    tcs = new_tcs;
+                    let record = head.ast.clone().apply_elim(elims.clone());
+                    ty = match tcs.def(*proj_def) {
+                        Decl::Proj(proj) => proj.instantiate(codata_args, record),
+                        _ => unreachable!(),
+                    };
+                    elims.push(Elim::Proj(ident.text.clone()));
                 }
-                e => return Err(TCE::NotProj(e)),
+                Abs::Implicit(name, _) => return Err(TCE::UnexpectedImplicit(name.clone())),
+                e => return Err(TCE::NotProj(e.clone())),
             },
         }
     }
     Ok((head.map_ast(|t| t.apply_elim(elims)), ty, tcs))
 }
 
+/// The whnf of `term`, or the unsolved meta it's blocked on.
+fn whnf_or_blocker(tcs: &TCS, term: Term) -> TCM<Result<Val, MI>> {
+    match simplify_ref(tcs, term) {
+        Ok(Val::Meta(mi, _)) if is_unsolved(tcs, mi) => Ok(Err(mi)),
+        Ok(val) => Ok(Ok(val)),
+        Err(TCE::Blocked(blocked)) => match blocked.is_meta().filter(|mi| is_unsolved(tcs, *mi)) {
+            Some(mi) => Ok(Err(mi)),
+            None => Err(TCE::Blocked(blocked)),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// The result of the postponed eliminations is a meta of a meta type,
+/// solved when the eliminations are done.
+fn postpone_elims(
+    mut tcs: TCS,
+    head: TermInfo,
+    ty: Term,
+    fun: &Abs,
+    args: Vec<Abs>,
+    blocker: MI,
+) -> InferTCM {
+    let loc = (args.iter()).fold(head.loc, |loc, arg| loc + arg.loc());
+    let expr = (once(head.ast.to_string()))
+        .chain(args.iter().map(ToString::to_string))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let origin = MetaOrigin::Universe(expr.clone());
+    let level = tcs.fresh_meta(Term::Whnf(Val::LevelType), loc, origin);
+    let universe = Term::universe(Level::atom(level));
+    let origin = MetaOrigin::Postponed(expr);
+    let term_ty = tcs.fresh_meta(universe, loc, origin.clone());
+    let term = tcs.fresh_meta(term_ty.clone(), loc, origin);
+    let problem = Problem::Elims {
+        head: head.ast,
+        ty,
+        fun: Box::new(fun.clone()),
+        args,
+        term: term.clone(),
+        term_ty: term_ty.clone(),
+    };
+    let tcs = postpone_problem(tcs, problem, blocker)?;
+    Ok((term.at(loc), term_ty, tcs))
+}
+
 pub fn type_of_decl(tcs: &TCS, decl: GI) -> TCM<TermInfo> {
     let decl = tcs.def(decl);
     match decl {
         Decl::Data(DataInfo {
+            loc,
+            params,
+            indices,
+            level,
+            ..
+        }) => {
+            let tele = params.iter().chain(indices).cloned().collect();
+            let level = level.clone().reduce_dbi(Subst::raise(DBI(indices.len())));
+            Ok(Term::pi_from_tele(tele, Term::universe(level)).at(*loc))
+        }
+        Decl::Codata(CodataInfo {
             loc, params, level, ..
-        })
-        | Decl::Codata(CodataInfo {
-            loc, params, level, ..
-        }) => Ok(Term::pi_from_tele(params.clone(), Term::universe(*level)).at(*loc)),
+        }) => Ok(Term::pi_from_tele(params.clone(), Term::universe(level.clone())).at(*loc)),
         Decl::Cons(cons) => {
             let params = &cons.params;
             let data = cons.data;
             let data_tele = match tcs.def(data) {
                 Decl::Data(i) => &i.params,
+                Decl::Codata(i) => &i.params,
                 _ => unreachable!(),
             };
             let params_len = params.len();
@@ -126,12 +280,13 @@ pub fn type_of_decl(tcs: &TCS, decl: GI) -> TCM<TermInfo> {
             let tele = data_tele
                 .iter()
                 .cloned()
-                // Because we have no GADT (
                 .map(Bind::into_implicit)
                 .chain(params.iter().cloned())
                 .collect();
             let ident = tcs.def(data).def_name().clone();
-            let elims = range.rev().map(DBI).map(Elim::from_dbi).collect();
+            let elims = (range.rev().map(DBI).map(Elim::from_dbi))
+                .chain(cons.indices.iter().cloned().map(Elim::app))
+                .collect();
             let ret = Term::def(data, ident, elims);
             Ok(Term::pi_from_tele(tele, ret).at(cons.loc()))
         }
@@ -140,16 +295,20 @@ pub fn type_of_decl(tcs: &TCS, decl: GI) -> TCM<TermInfo> {
                 Decl::Codata(i) => &i.params,
                 _ => unreachable!(),
             };
-            let range = 0..data_tele.len() - 1;
+            let range = 0..data_tele.len();
             let ident = tcs.def(proj.codata).def_name().clone();
             let elims = range.rev().map(DBI).map(Elim::from_dbi).collect();
             let codata = Term::def(proj.codata, ident, elims);
             let bind = Bind::new(Plicit::Ex, unsafe { next_uid() }, codata);
             let tele = (data_tele.iter().cloned())
-                // Or maybe we shouldn't?
+                // Like the ones of constructors, the parameters are implicit
+                // as they're determined by the type of `self`,
+                // and can still be given like `fst {A = Nat} p`.
                 .map(Bind::into_implicit)
                 .chain(once(bind))
                 .collect();
+            // `proj.ty` is in the context of the parameters and `self`,
+            // which is exactly `tele`.
             Ok(Term::pi_from_tele(tele, proj.ty.clone()).at(proj.loc()))
         }
         Decl::Func(func) => Ok(func.signature.clone().at(func.loc)),
@@ -185,6 +344,42 @@ fn infer_head_impl(tcs: TCS, abs: &Abs) -> InferTCM {
             let bind = tcs.local_by_id(*var);
             Ok((bind.val.at(loc.loc), bind.bind.ty, tcs))
         }
+        LSuc(id) => Ok((lsuc().at(id.loc), level_fun_type(1), tcs)),
+        LMax(id) => Ok((lmax().at(id.loc), level_fun_type(2), tcs)),
         e => Err(TCE::NotHead(e.clone())),
     }
 }
+
+/// `\l. l + 1`.
+fn lsuc() -> Term {
+    let body = Level::atom(Term::from_dbi(DBI(0))).lift(1);
+    level_fun(1, Term::level(body))
+}
+
+/// `\a b. max(a, b)`.
+fn lmax() -> Term {
+    let (a, b) = (Term::from_dbi(DBI(1)), Term::from_dbi(DBI(0)));
+    let body = Level::atom(a).max(Level::atom(b));
+    level_fun(2, Term::level(body))
+}
+
+fn level_bind() -> Bind<Box<Term>> {
+    Bind::boxing(
+        Plicit::Ex,
+        unsafe { next_uid() },
+        Term::Whnf(Val::LevelType),
+    )
+}
+
+/// `\l0 .. ln. body`, where the parameters are levels.
+fn level_fun(arity: usize, body: Term) -> Term {
+    (0..arity).fold(body, |body, _| {
+        Term::lam(level_bind(), Closure::plain(body))
+    })
+}
+
+/// `Level -> .. -> Level`.
+fn level_fun_type(arity: usize) -> Term {
+    let ret = Term::Whnf(Val::LevelType);
+    (0..arity).fold(ret, |ret, _| Term::pi2(level_bind(), Closure::plain(ret)))
+}
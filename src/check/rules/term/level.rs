@@ -0,0 +1,123 @@
+use voile_util::uid::{next_uid, DBI};
+
+use crate::{
+    check::{
+        monad::{MetaSol, TCE, TCM, TCS},
+        rules::term::simplify_ref,
+    },
+    syntax::core::{
+        subst::{RedEx, Subst},
+        Closure, Decl, FoldVal, Level, Tele, Term, Val,
+    },
+};
+
+/// Instantiate the solved metas and reduce the atoms of a level,
+/// so that the atoms of the result are neutral.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Level.html#levelView).
+pub fn level_view(tcs: &TCS, level: Level) -> TCM<Level> {
+    let (c, atoms) = match level.clone() {
+        Level::Omega => return Ok(Level::Omega),
+        Level::Max(c, atoms) => (c, atoms),
+    };
+    let mut viewed = Vec::with_capacity(atoms.len());
+    for (k, atom) in atoms {
+        let atom = match instantiate(tcs, atom) {
+            Term::Whnf(val) => Term::Whnf(val),
+            redex => match simplify_ref(tcs, redex.clone()) {
+                Ok(val) => Term::Whnf(val),
+                Err(TCE::Blocked(..)) => redex,
+                Err(e) => return Err(e),
+            },
+        };
+        viewed.push((k, atom));
+    }
+    let viewed = Level::Max(c, viewed).normalize();
+    // The solutions may be levels with solved metas as well.
+    if viewed != level {
+        level_view(tcs, viewed)
+    } else {
+        Ok(viewed)
+    }
+}
+
+/// The level of a term of type `Level`.
+pub fn term_level(tcs: &TCS, term: Term) -> TCM<Level> {
+    level_view(tcs, Level::atom(term))
+}
+
+/// Replace a solved meta with its solution.
+pub(super) fn instantiate(tcs: &TCS, term: Term) -> Term {
+    let (mi, elims) = match term {
+        Term::Whnf(Val::Meta(mi, elims)) => (mi, elims),
+        term => return term,
+    };
+    match tcs.meta_ctx().solution(mi) {
+        MetaSol::Solved(ix, sol) if *ix <= tcs.unify_depth => {
+            let sol = sol.clone().reduce_dbi(Subst::raise(tcs.unify_depth - *ix));
+            sol.apply_elim(elims)
+        }
+        _ => Term::meta(mi, elims),
+    }
+}
+
+/// The universe `val` lives in, if it's a type.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Sort.html#sortOf).
+pub fn sort_of(tcs: &TCS, val: &Val) -> Option<Level> {
+    sort_under(tcs, &mut Vec::new(), val)
+}
+
+/// Like [`sort_of`](self::sort_of), under the binders `locals`,
+/// the last of which is the variable of DBI `0`.
+fn sort_under(tcs: &TCS, locals: &mut Tele, val: &Val) -> Option<Level> {
+    match val {
+        Val::Type(level) => Some(level.clone().lift(1)),
+        Val::LevelType => Some(Level::default()),
+        Val::Data(data) => {
+            let (level, param_len) = match tcs.def(data.def) {
+                Decl::Data(info) => (info.level.clone(), info.params.len()),
+                Decl::Codata(info) => (info.level.clone(), info.params.len()),
+                _ => return None,
+            };
+            // The level doesn't depend on the indices.
+            let args = Subst::parallel(data.args.iter().take(param_len).rev().cloned());
+            Some(level.reduce_dbi(args))
+        }
+        Val::Id(ty, ..) => sort_under(tcs, locals, &simplify_ref(tcs, *ty.clone()).ok()?),
+        Val::Pi(bind, Closure::Plain(ret)) => {
+            let param = simplify_ref(tcs, *bind.ty.clone()).ok()?;
+            let param = sort_under(tcs, locals, &param)?;
+            locals.push(bind.clone().unboxed());
+            let ret = simplify_ref(tcs, *ret.clone()).ok();
+            let ret = ret.and_then(|ret| sort_under(tcs, locals, &ret));
+            locals.pop();
+            // Out of the binder, unless the level depends on the parameter.
+            let param_var = unsafe { next_uid() };
+            let ret = ret?.reduce_dbi(Subst::one(Term::postulate(param_var)));
+            let dependent = ret.atoms().any(|atom| {
+                let found = atom.try_fold_val((), |(), v| match v {
+                    Val::Axiom(uid) if *uid == param_var => Err(()),
+                    _ => Ok(()),
+                });
+                found.is_err()
+            });
+            if dependent {
+                None
+            } else {
+                Some(param.max(ret))
+            }
+        }
+        // The types of the variables bound during the conversion check are unknown.
+        Val::Var(ix, elims) if elims.is_empty() && tcs.unify_depth == DBI(0) => {
+            let ty = match locals.len().checked_sub(ix.0 + 1) {
+                Some(i) => locals[i].ty.clone(),
+                None => tcs.local(DBI(ix.0 - locals.len())).ty.clone(),
+            };
+            let ty = ty.reduce_dbi(Subst::raise(DBI(ix.0 + 1)));
+            match simplify_ref(tcs, ty).ok()? {
+                Val::Type(level) => Some(level),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -7,7 +7,8 @@ use crate::{
     },
     syntax::{
         common::Bind,
-        core::{Closure, Elim, Term, Val, ValData},
+        core::{Clause, Closure, Elim, Level, Term, Val, ValData},
+        pat::{Copat, Pat},
     },
 };
 
@@ -62,6 +63,20 @@ impl HasMeta for Term {
     }
 }
 
+impl HasMeta for Level {
+    fn inline_meta(self, tcs: TCS) -> TCMS<Self> {
+        match self {
+            Level::Omega => Ok((Level::Omega, tcs)),
+            Level::Max(c, atoms) => {
+                let (ks, atoms): (Vec<_>, Vec<_>) = atoms.into_iter().unzip();
+                let (atoms, tcs) = atoms.inline_meta(tcs)?;
+                let atoms = ks.into_iter().zip(atoms).collect();
+                Ok((Level::Max(c, atoms).normalize(), tcs))
+            }
+        }
+    }
+}
+
 impl<T: HasMeta> HasMeta for Bind<T> {
     fn inline_meta(self, tcs: TCS) -> TCMS<Self> {
         let (ty, tcs) = self.ty.inline_meta(tcs)?;
@@ -69,6 +84,53 @@ impl<T: HasMeta> HasMeta for Bind<T> {
     }
 }
 
+impl<Ix, T: HasMeta> HasMeta for Pat<Ix, T> {
+    fn inline_meta(self, tcs: TCS) -> TCMS<Self> {
+        match self {
+            Pat::Cons(forced, head, pats) => {
+                let (pats, tcs) = pats.inline_meta(tcs)?;
+                Ok((Pat::Cons(forced, head, pats), tcs))
+            }
+            Pat::Forced(t) => t.inline_meta(tcs).map(|(t, tcs)| (Pat::Forced(t), tcs)),
+            pat => Ok((pat, tcs)),
+        }
+    }
+}
+
+impl<Ix, T: HasMeta> HasMeta for Copat<Ix, T> {
+    fn inline_meta(self, tcs: TCS) -> TCMS<Self> {
+        match self {
+            Copat::App(pat) => pat.inline_meta(tcs).map(|(p, tcs)| (Copat::App(p), tcs)),
+            Copat::Implicit(name, pat) => {
+                (pat.inline_meta(tcs)).map(|(p, tcs)| (Copat::Implicit(name, p), tcs))
+            }
+            Copat::Proj(field) => Ok((Copat::Proj(field), tcs)),
+        }
+    }
+}
+
+impl HasMeta for Clause {
+    fn inline_meta(self, tcs: TCS) -> TCMS<Self> {
+        let (pat_tele, tcs) = self.pat_tele.inline_meta(tcs)?;
+        let (patterns, mut tcs) = self.patterns.inline_meta(tcs)?;
+        let body = match self.body {
+            Some(body) => {
+                let (body, new_tcs) = body.inline_meta(tcs)?;
+                tcs = new_tcs;
+                Some(body)
+            }
+            None => None,
+        };
+        let clause = Clause {
+            pat_tele,
+            patterns,
+            body,
+            ..self
+        };
+        Ok((clause, tcs))
+    }
+}
+
 impl HasMeta for Closure {
     fn inline_meta(self, mut tcs: TCS) -> TCMS<Self> {
         tcs.unify_depth += 1;
@@ -87,23 +149,34 @@ fn solve_meta(tcs: TCS, mi: MI, elims: Vec<Elim>) -> TCMS<Term> {
     use MetaSol::*;
     let (_ix, sol) = match tcs.meta_ctx().solution(mi) {
         Solved(ix, sol) => (*ix, sol.clone()),
-        Unsolved => return Err(TCE::MetaUnsolved(mi)),
+        Unsolved => {
+            let info = tcs.meta_ctx().info(mi).clone();
+            return Err(TCE::MetaUnsolved(mi, Box::new(info)));
+        }
     };
     let (elims, tcs) = elims.inline_meta(tcs)?;
-    Ok((sol.apply_elim(elims), tcs))
+    // The solution may refer to other metas.
+    sol.apply_elim(elims).inline_meta(tcs)
 }
 
 impl HasMeta for Val {
     fn inline_meta(self, tcs: TCS) -> TCMS<Self> {
         use Val::*;
         match self {
-            Type(l) => Ok((Type(l), tcs)),
+            Type(l) => l.inline_meta(tcs).map(|(l, tcs)| (Type(l), tcs)),
+            LevelType => Ok((LevelType, tcs)),
+            Level(l) => l.inline_meta(tcs).map(|(l, tcs)| (Level(l), tcs)),
             Data(info) => info.inline_meta(tcs).map(|(i, tcs)| (Data(i), tcs)),
             Pi(t, clos) => {
                 let (t, tcs) = t.unboxed().inline_meta(tcs)?;
                 let (clos, tcs) = clos.inline_meta(tcs)?;
                 Ok((Val::Pi(t.boxed(), clos), tcs))
             }
+            Lam(t, clos) => {
+                let (t, tcs) = t.unboxed().inline_meta(tcs)?;
+                let (clos, tcs) = clos.inline_meta(tcs)?;
+                Ok((Val::Lam(t.boxed(), clos), tcs))
+            }
             Cons(c, ts) => ts.inline_meta(tcs).map(|(ts, tcs)| (Cons(c, ts), tcs)),
             Meta(mi, elims) => {
                 let (sol, tcs) = solve_meta(tcs, mi, elims)?;
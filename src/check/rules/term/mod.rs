@@ -1,23 +1,36 @@
-use voile_util::loc::ToLoc;
+use voile_util::{
+    loc::ToLoc,
+    meta::MI,
+    tags::Plicit,
+    uid::{next_uid, DBI},
+};
 
 use crate::{
-    check::monad::{TermTCM, TCE, TCS},
+    check::monad::{MetaOrigin, Problem, TermTCM, TCE, TCS},
     syntax::{
         abs::Abs,
-        core::{Bind, Closure, Term, Val},
+        core::{
+            subst::{RedEx, Subst},
+            Bind, Closure, Level, Term, Val,
+        },
     },
 };
 
 pub use self::{
     infer::{infer, type_of_decl},
+    level::{level_view, sort_of, term_level},
     meta::HasMeta,
-    unify::subtype,
+    unify::{meta_to_pi, solve_level_bounds, subtype, unify, unify_at},
     view::{expect_data, is_eta_var, is_eta_var_ref},
-    whnf::simplify,
+    whnf::{simplify, simplify_ref},
 };
 
+use self::unify::{is_unsolved, postpone_problem};
+
 /// Synthesize the type and its well-typed form from an abstract term.
 mod infer;
+/// Universe levels and the universes of types.
+mod level;
 /// Solves meta variables inside a term and things.
 mod meta;
 /// Conversion check.
@@ -47,32 +60,116 @@ pub fn check(mut tcs: TCS, input_term: &Abs, against: &Val) -> TermTCM {
     Ok((a, tcs))
 }
 
-fn check_impl(tcs: TCS, abs: &Abs, against: &Val) -> TermTCM {
+fn check_impl(mut tcs: TCS, abs: &Abs, against: &Val) -> TermTCM {
     match (abs, against) {
         (Abs::Type(info, lower), Val::Type(upper)) => {
-            if upper > lower {
-                Ok((Term::universe(*lower).at(info.loc), tcs))
+            let level = Level::from(*lower).lift(1);
+            let tcs = if level.leq(upper) {
+                tcs
             } else {
-                Err(TCE::DifferentLevel(abs.loc(), *lower + 1, *upper))
-            }
+                let err = || TCE::DifferentLevel(abs.loc(), level.clone(), upper.clone());
+                subtype(tcs, &Val::Type(level.clone()), against).map_err(|_| err())?
+            };
+            Ok((Term::universe(*lower).at(info.loc), tcs))
         }
-        (Abs::Pi(info, bind, ret), Val::Type(..)) => {
+        (Abs::Pi(info, bind, ret), Val::Type(level)) => {
             // Because `against` is `Val::Type(level)`
             let (bind_ty, mut tcs) = check(tcs, &*bind.ty, against)?;
             let new = Bind::new(bind.licit, bind.name, bind_ty.ast);
             tcs.gamma.push(new);
-            let (ret_ty, mut tcs) = check(tcs, &**ret, against)?;
+            let against = Val::Type(level.clone().reduce_dbi(Subst::raise(DBI(1))));
+            let (ret_ty, mut tcs) = check(tcs, &**ret, &against)?;
             let bind_ty = tcs.gamma.pop().expect("Bad index");
             let term = Term::pi2(bind_ty.boxed(), Closure::plain(ret_ty.ast));
             Ok((term.at(*info), tcs))
         }
+        (Abs::Lam(param, uid, body), Val::Pi(bind, Closure::Plain(ret))) => {
+            // Insert an implicit lambda if the lambda is explicit
+            let (name, inserted) = match bind.licit {
+                Plicit::Ex => (*uid, false),
+                Plicit::Im => (unsafe { next_uid() }, true),
+            };
+            let param_ty = Bind::new(bind.licit, name, *bind.ty.clone());
+            tcs.gamma.push(param_ty);
+            let body = if inserted { abs } else { &**body };
+            let (body, mut tcs) = check_term(tcs, body, *ret.clone())?;
+            let param_ty = tcs.gamma.pop().expect("Bad index");
+            let lam = Term::lam(param_ty.boxed(), Closure::plain(body.ast));
+            Ok((lam.at(param.loc + body.loc), tcs))
+        }
+        // We need to know the type is a function or an identity type.
+        (Abs::Lam(..), Val::Meta(mi, _)) | (Abs::Refl(..), Val::Meta(mi, _))
+            if is_unsolved(&tcs, *mi) =>
+        {
+            postpone_check(tcs, abs, Term::Whnf(against.clone()), *mi)
+        }
+        (Abs::Lam(..), anything) => Err(TCE::NotPi(Term::Whnf(anything.clone()), abs.loc())),
+        (Abs::Id(a, b), Val::Type(upper)) => {
+            let (a, ty, tcs) = infer(tcs, a)?;
+            // The type of the sides must be in the expected universe,
+            // unless its universe is not known yet (like a meta).
+            let sort = simplify_ref(&tcs, ty.clone()).ok();
+            let tcs = match sort.and_then(|ty| sort_of(&tcs, &ty)) {
+                Some(level) if !level.leq(upper) => {
+                    let err = || TCE::DifferentLevel(abs.loc(), level.clone(), upper.clone());
+                    subtype(tcs, &Val::Type(level.clone()), against).map_err(|_| err())?
+                }
+                _ => tcs,
+            };
+            let (b, tcs) = check_term(tcs, b, ty.clone())?;
+            let id = Term::identity(ty, a.ast, b.ast);
+            Ok((id.at(abs.loc()), tcs))
+        }
+        (Abs::Refl(info), Val::Id(ty, a, b)) => {
+            let tcs = unify_at(tcs, ty, a, b).map_err(|e| e.wrap(info.loc))?;
+            Ok((Term::reflexivity().at(info.loc), tcs))
+        }
+        (Abs::Refl(info), anything) => Err(TCE::not_id(anything.clone()).wrap(info.loc)),
+        (Abs::Meta(ident, _), anything) => {
+            let origin = MetaOrigin::User(ident.text.clone());
+            let meta = tcs.fresh_meta(Term::Whnf(anything.clone()), ident.loc, origin);
+            Ok((meta.at(ident.loc), tcs))
+        }
         (expr, anything) => check_fallback(tcs, expr.clone(), anything),
     }
 }
 
+/// Check `abs` against `ty` (which is not simplified yet), or postpone it
+/// if `ty` is blocked on a meta.
+pub fn check_term(tcs: TCS, abs: &Abs, ty: Term) -> TermTCM {
+    match simplify_ref(&tcs, ty.clone()) {
+        Ok(val) => check(tcs, abs, &val),
+        Err(TCE::Blocked(blocked)) => match blocked.is_meta().filter(|mi| is_unsolved(&tcs, *mi)) {
+            Some(mi) => postpone_check(tcs, abs, ty, mi),
+            None => Err(TCE::Blocked(blocked)),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// The checked term is a meta, solved when the check is done.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Rules.Term.html#postponeTypeCheckingProblem).
+fn postpone_check(mut tcs: TCS, abs: &Abs, ty: Term, blocker: MI) -> TermTCM {
+    let origin = MetaOrigin::Postponed(abs.to_string());
+    let meta = tcs.fresh_meta(ty.clone(), abs.loc(), origin);
+    let problem = Problem::Check(abs.clone(), ty, meta.clone());
+    let tcs = postpone_problem(tcs, problem, blocker)?;
+    Ok((meta.at(abs.loc()), tcs))
+}
+
 pub fn check_fallback(tcs: TCS, expr: Abs, expected_type: &Val) -> TermTCM {
     let (evaluated, inferred, tcs) = infer(tcs, &expr)?;
-    let (whnf, tcs) = simplify(tcs, inferred)?;
+    let (mut whnf, mut tcs) = simplify(tcs, inferred)?;
+    // The type of a type is a universe, at a level we don't know yet.
+    if let (Val::Meta(..), Val::Type(..)) = (&whnf, expected_type) {
+        let origin = MetaOrigin::Universe(expr.to_string());
+        let level = tcs.fresh_meta(Term::Whnf(Val::LevelType), expr.loc(), origin);
+        let universe = Val::Type(Level::atom(level));
+        tcs = unify(tcs, &Term::Whnf(whnf), &Term::Whnf(universe.clone()))?;
+        whnf = universe;
+    }
+    let (evaluated, whnf, tcs) =
+        infer::insert_implicits(tcs, &expr, evaluated, whnf, expected_type)?;
     let tcs = subtype(tcs, &whnf, expected_type).map_err(|e| e.wrap(expr.loc()))?;
     Ok((evaluated, tcs))
 }
@@ -1,19 +1,30 @@
 use std::cmp::Ordering;
 
-use voile_util::{meta::MI, uid::GI};
+use voile_util::{
+    loc::{Loc, ToLoc},
+    meta::MI,
+    tags::Plicit,
+    uid::{next_uid, DBI, GI, UID},
+};
 
 use crate::{
     check::{
-        monad::{MetaSol, TCE, TCM, TCS},
-        rules::term::simplify,
+        monad::{Comparison, Constraint, MetaOrigin, MetaSol, Problem, TCE, TCM, TCMS, TCS},
+        rules::term::{
+            check_term, infer::infer_elims, level::instantiate, level_view, simplify_ref, sort_of,
+            type_of_decl,
+        },
     },
-    syntax::core::{
-        subst::{RedEx, Subst},
-        Closure, Elim, FoldVal, Term, Val, ValData,
+    syntax::{
+        common::ConHead,
+        core::{
+            subst::{DeBruijn, RedEx, Subst},
+            Bind, Closure, Decl, Elim, FoldVal, Level, Term, Val, ValData,
+        },
     },
 };
 
-fn check_solution(meta: MI, rhs: &Val) -> TCM<()> {
+fn check_solution(meta: MI, rhs: &Term) -> TCM<()> {
     rhs.try_fold_val((), |(), v| match v {
         Val::Meta(mi, ..) if mi == &meta => Err(TCE::MetaRecursion(*mi)),
         _ => Ok(()),
@@ -38,8 +49,23 @@ pub fn subtype(mut tcs: TCS, sub: &Val, sup: &Val) -> TCM {
 
 fn subtype_impl(mut tcs: TCS, sub: &Val, sup: &Val) -> TCM {
     use Val::*;
+    // The solution of a meta may be in a smaller universe.
+    let instantiated = |v: &Val| match v {
+        Meta(..) => simplify_ref(&tcs, instantiate(&tcs, Term::Whnf(v.clone())))
+            .ok()
+            .filter(|i| i != v),
+        _ => None,
+    };
+    match (instantiated(sub), instantiated(sup)) {
+        (None, None) => {}
+        (a, b) => {
+            let a = a.unwrap_or_else(|| sub.clone());
+            let b = b.unwrap_or_else(|| sup.clone());
+            return subtype(tcs, &a, &b);
+        }
+    }
     match (sub, sup) {
-        (Type(sub_l), Type(sup_l)) if sub_l <= sup_l => Ok(tcs),
+        (Type(sub_l), Type(sup_l)) => subtype_level(tcs, sub_l, sup_l),
         (Pi(a, c0), Pi(b, c1)) if a.licit == b.licit => {
             tcs = Unify::unify(tcs, &a.ty, &b.ty)?;
             compare_closure(tcs, c0, c1, |tcs, a, b| match (a, b) {
@@ -52,6 +78,89 @@ fn subtype_impl(mut tcs: TCS, sub: &Val, sup: &Val) -> TCM {
     }
 }
 
+/// Conversion check on terms, reducing them when they're not syntactically equal.
+pub fn unify(tcs: TCS, left: &Term, right: &Term) -> TCM {
+    Unify::unify(tcs, left, right)
+}
+
+/// Type-directed conversion check on terms of type `ty`, which eta-expands
+/// functions and values of codata types with eta-equality.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Conversion.html#compareTerm%27).
+pub fn unify_at(mut tcs: TCS, ty: &Term, left: &Term, right: &Term) -> TCM {
+    // Eta-expanding a meta would prevent it from being solved.
+    let is_meta = |t: &Term| matches!(t, Term::Whnf(Val::Meta(..)));
+    if left == right || is_meta(left) || is_meta(right) {
+        return unify(tcs, left, right);
+    }
+    let ty = match simplify_ref(&tcs, ty.clone()) {
+        Ok(ty) => ty,
+        Err(TCE::Blocked(..)) => return unify(tcs, left, right),
+        Err(e) => return Err(e),
+    };
+    match ty {
+        Val::Pi(_, Closure::Plain(ret)) => {
+            let app = |t: &Term| {
+                let t = t.clone().reduce_dbi(Subst::raise(DBI(1)));
+                t.apply(vec![Term::from_dbi(DBI(0))])
+            };
+            tcs.unify_depth += 1;
+            tcs = unify_at(tcs, &ret, &app(left), &app(right))?;
+            tcs.unify_depth -= 1;
+            Ok(tcs)
+        }
+        Val::Data(data) => {
+            let mut projs = match tcs.def(data.def) {
+                Decl::Codata(info) if info.eta => (info.fields.iter())
+                    .map(|(name, ix)| (*ix, name.clone()))
+                    .collect::<Vec<_>>(),
+                _ => return unify(tcs, left, right),
+            };
+            projs.sort();
+            for (ix, name) in projs {
+                let proj_ty = match tcs.def(ix) {
+                    Decl::Proj(proj) => proj.instantiate(data.args.clone(), left.clone()),
+                    _ => unreachable!(),
+                };
+                let proj = |t: &Term| t.clone().apply_elim(vec![Elim::Proj(name.clone())]);
+                tcs = unify_at(tcs, &proj_ty, &proj(left), &proj(right))?;
+            }
+            Ok(tcs)
+        }
+        _ => unify(tcs, left, right),
+    }
+}
+
+/// Compare the eliminations `left` and `right` of `head`, whose type is `ty`,
+/// so that the arguments are compared with their types.
+fn unify_spine(mut tcs: TCS, mut ty: Term, mut head: Term, left: &[Elim], right: &[Elim]) -> TCM {
+    for (i, (a, b)) in left.iter().zip(right).enumerate() {
+        let ty_val = match simplify_ref(&tcs, ty) {
+            Ok(val) => val,
+            Err(TCE::Blocked(..)) => return Unify::unify(tcs, &left[i..], &right[i..]),
+            Err(e) => return Err(e),
+        };
+        ty = match (ty_val, a, b) {
+            (Val::Pi(bind, clos), Elim::App(a), Elim::App(b)) => {
+                tcs = unify_at(tcs, &bind.ty, a, b)?;
+                clos.instantiate(*a.clone())
+            }
+            (Val::Data(data), Elim::Proj(f), Elim::Proj(g)) if f == g => {
+                let ix = match tcs.def(data.def) {
+                    Decl::Codata(info) => info.fields[f],
+                    _ => unreachable!(),
+                };
+                match tcs.def(ix) {
+                    Decl::Proj(proj) => proj.instantiate(data.args, head.clone()),
+                    _ => unreachable!(),
+                }
+            }
+            _ => return Unify::unify(tcs, &left[i..], &right[i..]),
+        };
+        head = head.apply_elim(vec![a.clone()]);
+    }
+    Ok(tcs)
+}
+
 trait Unify {
     /// Conversion check, maybe can solve metas.
     fn unify(tcs: TCS, left: &Self, right: &Self) -> TCM;
@@ -73,15 +182,43 @@ impl<T: Unify> Unify for Box<T> {
 }
 
 impl Unify for Term {
-    fn unify(mut tcs: TCS, left: &Self, right: &Self) -> TCM {
+    fn unify(tcs: TCS, left: &Self, right: &Self) -> TCM {
         use Term::*;
         match (left, right) {
+            (a, b) if a == b => Ok(tcs),
             (Whnf(left), Whnf(right)) => Unify::unify(tcs, left, right),
-            (Redex(i, _, a), Redex(j, _, b)) if a.len() == b.len() => {
-                tcs = Unify::unify(tcs, i, j)?;
-                Unify::unify(tcs, a.as_slice(), b.as_slice())
+            (Redex(i, id, a), Redex(j, _, b)) if i == j && a.len() == b.len() => {
+                let ty = type_of_decl(&tcs, *i)?.ast;
+                unify_spine(tcs, ty, Term::simple_def(*i, id.clone()), a, b)
+            }
+            // A blocked term can still be a solution.
+            (Whnf(Val::Meta(i, a)), b) | (b, Whnf(Val::Meta(i, a))) => {
+                unify_meta_with(tcs, b, *i, a)
             }
-            (a, b) => Err(TCE::different_term(a.clone(), b.clone())),
+            // Try again after reduction.
+            (a, b) => match (simplify_ref(&tcs, a.clone()), simplify_ref(&tcs, b.clone())) {
+                (Ok(a), Ok(b)) => Unify::unify(tcs, &a, &b),
+                // Stuck terms are neutral as well.
+                (Ok(Val::Cons(c, args)), Err(TCE::Blocked(blocked)))
+                | (Err(TCE::Blocked(blocked)), Ok(Val::Cons(c, args)))
+                    if is_eta_record(&tcs, &c) =>
+                {
+                    unify_record_eta(tcs, &c, &args, &blocked.anyway)
+                }
+                // Compare the terms they're stuck at, like `mk x y .fst` at `x`.
+                (Err(TCE::Blocked(x)), Err(TCE::Blocked(y)))
+                    if (&x.anyway, &y.anyway) != (a, b) =>
+                {
+                    Unify::unify(tcs, &x.anyway, &y.anyway)
+                }
+                (Err(TCE::Blocked(blocked)), _) | (_, Err(TCE::Blocked(blocked))) => {
+                    match blocked.is_meta().filter(|mi| is_unsolved(&tcs, *mi)) {
+                        Some(mi) => postpone(tcs, Comparison::Eq, a, b, mi),
+                        None => Err(TCE::different_term(a.clone(), b.clone())),
+                    }
+                }
+                _ => Err(TCE::different_term(a.clone(), b.clone())),
+            },
         }
     }
 }
@@ -133,7 +270,10 @@ impl Unify for Closure {
 impl Unify for ValData {
     fn unify(tcs: TCS, left: &Self, right: &Self) -> TCM {
         let tcs = Unify::unify(tcs, &left.def, &right.def)?;
-        Unify::unify(tcs, left.args.as_slice(), right.args.as_slice())
+        let ty = type_of_decl(&tcs, left.def)?.ast;
+        let head = Term::simple_def(left.def, tcs.def(left.def).def_name().clone());
+        let elims = |args: &[Term]| args.iter().cloned().map(Elim::app).collect::<Vec<_>>();
+        unify_spine(tcs, ty, head, &elims(&left.args), &elims(&right.args))
     }
 }
 
@@ -143,33 +283,253 @@ impl Unify for Val {
     }
 }
 
-fn unify_meta_with(mut tcs: TCS, term: &Val, mi: MI) -> TCM {
+/// Is `c` the constructor of a record with eta-equality?
+fn is_eta_record(tcs: &TCS, c: &ConHead) -> bool {
+    match tcs.def(c.cons_ix) {
+        Decl::Cons(cons) => matches!(tcs.def(cons.data), Decl::Codata(info) if info.eta),
+        _ => false,
+    }
+}
+
+/// Eta for records, the neutral `r` is `c (r .f0) (r .f1)` where `c` is the
+/// record constructor with fields `f0` and `f1`.
+fn unify_record_eta(mut tcs: TCS, c: &ConHead, args: &[Term], r: &Term) -> TCM {
+    for (field, arg) in c.fields.iter().zip(args) {
+        let r = r.clone().apply_elim(vec![Elim::Proj(field.clone())]);
+        tcs = Unify::unify(tcs, arg, &r)?;
+    }
+    Ok(tcs)
+}
+
+pub(super) fn is_unsolved(tcs: &TCS, mi: MI) -> bool {
+    matches!(tcs.meta_ctx().solution(mi), MetaSol::Unsolved)
+}
+
+/// Postpone the comparison of `left` and `right` until `blocker` is solved.
+fn postpone(tcs: TCS, cmp: Comparison, left: &Term, right: &Term, blocker: MI) -> TCM {
+    let problem = Problem::Compare(cmp, left.clone(), right.clone());
+    postpone_problem(tcs, problem, blocker)
+}
+
+/// Postpone `problem` until `blocker` is solved.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Constraints.html#addConstraint).
+pub(super) fn postpone_problem(mut tcs: TCS, problem: Problem, blocker: MI) -> TCM {
+    if tcs.trace_tc {
+        println!("{}Postponed {}", tcs.tc_depth_ws(), problem);
+    }
+    let (gamma, lets) = (tcs.gamma.clone(), tcs.lets.clone());
+    let depth = tcs.unify_depth;
+    let constraint = Constraint::new(problem, blocker, gamma, lets, depth);
+    tcs.constraints.push(constraint);
+    Ok(tcs)
+}
+
+/// Try the constraints blocked on `mi` again, which is just solved.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Constraints.html#wakeConstraints).
+fn wake_up(mut tcs: TCS, mi: MI) -> TCM {
+    let (woken, rest) = (tcs.constraints.drain(..)).partition::<Vec<_>, _>(|c| c.blocker == mi);
+    tcs.constraints = rest;
+    for constraint in woken {
+        tcs = retry(tcs, constraint)?;
+    }
+    Ok(tcs)
+}
+
+/// Solve a postponed problem in its own context.
+fn retry(mut tcs: TCS, constraint: Constraint) -> TCM {
+    let gamma = std::mem::replace(&mut tcs.gamma, constraint.gamma);
+    let lets = std::mem::replace(&mut tcs.lets, constraint.lets);
+    let depth = std::mem::replace(&mut tcs.unify_depth, constraint.unify_depth);
+    tcs = match constraint.problem {
+        Problem::Compare(Comparison::Eq, left, right) => Unify::unify(tcs, &left, &right)?,
+        Problem::Compare(Comparison::Leq, left, right) => {
+            let left = simplify_ref(&tcs, left)?;
+            let right = simplify_ref(&tcs, right)?;
+            subtype(tcs, &left, &right)?
+        }
+        Problem::Check(abs, ty, term) => {
+            let (checked, tcs) = check_term(tcs, &abs, ty)?;
+            unify(tcs, &term, &checked.ast)?
+        }
+        Problem::Elims {
+            head,
+            ty,
+            fun,
+            args,
+            term,
+            term_ty,
+        } => {
+            let head = head.at(fun.loc());
+            let (elims, elims_ty, tcs) = infer_elims(tcs, head, ty, &fun, args)?;
+            let tcs = unify(tcs, &term_ty, &elims_ty)?;
+            unify(tcs, &term, &elims.ast)?
+        }
+    };
+    tcs.gamma = gamma;
+    tcs.lets = lets;
+    tcs.unify_depth = depth;
+    Ok(tcs)
+}
+
+/// Solve the level metas which are only bounded from below by the universes
+/// of the solutions of other metas, with the smallest level possible.
+/// Should be invoked before reporting the unsolved constraints.
+pub fn solve_level_bounds(mut tcs: TCS) -> TCM {
+    let is_leq = |c: &Constraint| matches!(c.problem, Problem::Compare(Comparison::Leq, ..));
+    while let Some(ix) = tcs.constraints.iter().position(is_leq) {
+        let mut constraint = tcs.constraints.remove(ix);
+        if let Problem::Compare(cmp, ..) = &mut constraint.problem {
+            *cmp = Comparison::Eq;
+        }
+        tcs = retry(tcs, constraint)?;
+    }
+    Ok(tcs)
+}
+
+/// The distinct variables `elims` consist of, if `elims` is a pattern.
+fn pattern_vars(tcs: &TCS, elims: &[Elim]) -> Option<Vec<DBI>> {
+    let mut vars = Vec::with_capacity(elims.len());
+    for elim in elims {
+        let arg = match elim {
+            Elim::App(arg) => simplify_ref(tcs, *arg.clone()).ok()?,
+            Elim::Proj(..) => return None,
+        };
+        match arg {
+            Val::Var(x, es) if es.is_empty() && !vars.contains(&x) => vars.push(x),
+            _ => return None,
+        }
+    }
+    Some(vars)
+}
+
+/// Can `?mi elims` be solved or instantiated?
+fn is_solvable(tcs: &TCS, mi: MI, elims: &[Elim]) -> bool {
+    match tcs.meta_ctx().solution(mi) {
+        MetaSol::Solved(..) => true,
+        MetaSol::Unsolved => pattern_vars(tcs, elims).is_some(),
+    }
+}
+
+/// Solve `?mi elims = term` where `elims` are distinct variables
+/// $x_1, \dots, x_n$, with $\lambda x_1 \dots x_n. \text{term}$.
+/// The other variables bound during the conversion check are not in the
+/// scope of the meta, so `term` should not mention them.
+/// The parameters of the lambdas are the ones of the meta's type, which
+/// should be a pi type of at least $n$ parameters. Otherwise it fails with
+/// [`MetaNotPattern`](crate::check::monad::TCE::MetaNotPattern), which
+/// postpones the check, like when `elims` are not distinct variables.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.MetaVars.html#assignMeta%27).
+fn pattern_solution(tcs: &TCS, mi: MI, elims: &[Elim], term: &Term) -> TCM<Term> {
+    let not_pattern = || TCE::meta_not_pattern(Term::meta(mi, elims.to_vec()), term.clone());
+    let vars = pattern_vars(tcs, elims).ok_or_else(not_pattern)?;
+    let params = (meta_type(tcs, mi).map(|ty| ty.tele_view().0))
+        .filter(|params| params.len() >= vars.len())
+        .ok_or_else(not_pattern)?;
+    let n = vars.len();
+    let DBI(depth) = tcs.unify_depth;
+    let len = (vars.iter().map(|DBI(x)| x + 1)).fold(depth, usize::max);
+    let out_of_scope = unsafe { next_uid() };
+    let renaming = (0..len).map(|x| match vars.iter().position(|&v| v == DBI(x)) {
+        Some(k) => Term::from_dbi(DBI(n - k - 1)),
+        None if x < depth => Term::postulate(out_of_scope),
+        None => Term::from_dbi(DBI(x + n)),
+    });
+    let body = (term.clone()).reduce_dbi(Subst::concat(renaming, Subst::raise(DBI(len + n))));
+    let scoped = body.try_fold_val((), |(), v| match v {
+        Val::Axiom(uid) if *uid == out_of_scope => Err(()),
+        _ => Ok(()),
+    });
+    if scoped.is_err() {
+        return Err(TCE::meta_scope(mi, term.clone()));
+    }
+    // The meta's type and the solution bind the parameters in the same way.
+    let sol = (params.into_iter().take(n)).rfold(body, |body, param| {
+        Term::lam(param.boxed(), Closure::plain(body))
+    });
+    Ok(sol)
+}
+
+/// The type of the meta `mi` in the current context,
+/// or `None` if we're out of the context where it's introduced.
+fn meta_type(tcs: &TCS, mi: MI) -> Option<Term> {
+    let info = tcs.meta_ctx().info(mi);
+    let shift = tcs.gamma.len().checked_sub(info.tele.len())? + tcs.unify_depth.0;
+    Some(info.ty.clone().reduce_dbi(Subst::raise(DBI(shift))))
+}
+
+/// Solve the meta `?mi`, which is a type introduced in the current context,
+/// with the function type `(x : ?A) -> ?B` of fresh metas in the same
+/// universe, where `x` is named `name`.
+/// Returns `None` if `?mi` is not such a type.
+pub fn meta_to_pi(
+    mut tcs: TCS,
+    mi: MI,
+    name: UID,
+    loc: Loc,
+    origin: MetaOrigin,
+) -> TCMS<Option<Term>> {
+    let info = tcs.meta_ctx().info(mi);
+    if info.tele.len() != tcs.gamma.len() {
+        return Ok((None, tcs));
+    }
+    let universe = match simplify_ref(&tcs, info.ty.clone()) {
+        Ok(universe @ Val::Type(..)) => Term::Whnf(universe),
+        _ => return Ok((None, tcs)),
+    };
+    let param = Bind::new(
+        Plicit::Ex,
+        name,
+        tcs.fresh_meta(universe.clone(), loc, origin.clone()),
+    );
+    tcs.gamma.push(param.clone());
+    let universe = universe.reduce_dbi(Subst::raise(DBI(1)));
+    let ret = tcs.fresh_meta(universe, loc, origin);
+    tcs.gamma.pop();
+    let pi = Term::pi2(param.boxed(), Closure::plain(ret));
+    let tcs = unify(tcs, &Term::meta(mi, vec![]), &pi)?;
+    Ok((Some(pi), tcs))
+}
+
+/// Solve or instantiate the meta application `?mi elims` with `term`.
+fn unify_meta_with(mut tcs: TCS, term: &Term, mi: MI, elims: &[Elim]) -> TCM {
     let depth = tcs.unify_depth;
     match tcs.meta_ctx().solution(mi) {
         MetaSol::Unsolved => {
-            check_solution(mi, term)?;
+            let sol = if elims.is_empty() {
+                term.clone()
+            } else {
+                match pattern_solution(&tcs, mi, elims, term) {
+                    Ok(sol) => sol,
+                    Err(TCE::MetaNotPattern(meta, term)) => {
+                        return postpone(tcs, Comparison::Eq, &meta, &term, mi);
+                    }
+                    Err(e) => return Err(e),
+                }
+            };
+            check_solution(mi, &sol)?;
             if tcs.trace_tc {
-                println!("{}?{} := {}", tcs.tc_depth_ws(), mi.0, term);
+                println!("{}?{} := {}", tcs.tc_depth_ws(), mi.0, sol);
             }
-            let solution = Term::Whnf(term.clone());
-            tcs.mut_meta_ctx().solve_meta(mi, depth, solution);
-            Ok(tcs)
+            tcs.mut_meta_ctx().solve_meta(mi, depth, sol.clone());
+            if elims.is_empty() {
+                tcs = check_meta_sort(tcs, mi, &sol)?;
+            }
+            wake_up(tcs, mi)
         }
         MetaSol::Solved(ix, sol) => match ix.cmp(&depth) {
             Ordering::Equal => {
-                let sol = *sol.clone();
-                let (sol, tcs) = simplify(tcs, sol)?;
+                let sol = sol.clone().apply_elim(elims.to_vec());
                 Unify::unify(tcs, &sol, term)
             }
             Ordering::Less => {
                 let sol = sol.clone().reduce_dbi(Subst::raise(depth - *ix));
-                let (sol, tcs) = simplify(tcs, sol)?;
-                Unify::unify(tcs, &sol, term)
+                Unify::unify(tcs, &sol.apply_elim(elims.to_vec()), term)
             }
             Ordering::Greater => {
                 let sol_ix = *ix;
-                let term = term.clone().reduce_dbi(Subst::raise(sol_ix - depth));
-                let sol = *sol.clone();
+                let raise = Subst::raise(sol_ix - depth);
+                let term = term.clone().reduce_dbi(raise.clone());
+                let sol = sol.clone().apply_elim(elims.to_vec().reduce_dbi(raise));
                 tcs.unify_depth = sol_ix;
                 tcs = Unify::unify(tcs, &sol, &term)?;
                 tcs.unify_depth = depth;
@@ -179,39 +539,133 @@ fn unify_meta_with(mut tcs: TCS, term: &Val, mi: MI) -> TCM {
     }
 }
 
+/// The solution of a meta of type `Type l` should be a type in a universe
+/// no bigger than `l`.
+fn check_meta_sort(tcs: TCS, mi: MI, sol: &Term) -> TCM {
+    let ty = match meta_type(&tcs, mi) {
+        Some(ty) => ty,
+        None => return Ok(tcs),
+    };
+    let (level, sol) = match (simplify_ref(&tcs, ty), simplify_ref(&tcs, sol.clone())) {
+        (Ok(Val::Type(level)), Ok(sol)) => (level, sol),
+        _ => return Ok(tcs),
+    };
+    match sort_of(&tcs, &sol) {
+        Some(sort) => subtype_level(tcs, &sort, &level),
+        None => Ok(tcs),
+    }
+}
+
+/// Cumulativity, `sub` should be smaller than or equal to `sup`.
+/// If `sup` mentions unsolved metas, wait for the other constraints on them,
+/// or they'll be solved by [`solve_level_bounds`](self::solve_level_bounds).
+fn subtype_level(tcs: TCS, sub: &Level, sup: &Level) -> TCM {
+    let sub = level_view(&tcs, sub.clone())?;
+    let sup = level_view(&tcs, sup.clone())?;
+    match unsolved_meta_atom(&tcs, &sup) {
+        Some(mi) => {
+            let (sub, sup) = (Term::universe(sub), Term::universe(sup));
+            postpone(tcs, Comparison::Leq, &sub, &sup, mi)
+        }
+        None if sub.leq(&sup) => Ok(tcs),
+        None => unify_level(tcs, &sub, &sup),
+    }
+}
+
+fn unsolved_meta_atom(tcs: &TCS, level: &Level) -> Option<MI> {
+    level.atoms().find_map(|atom| match atom {
+        Term::Whnf(Val::Meta(mi, _)) if is_unsolved(tcs, *mi) => Some(*mi),
+        _ => None,
+    })
+}
+
+/// Levels are equal if they're smaller than or equal to each other,
+/// and a meta plus a constant can be solved by the other level minus the constant.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Conversion.html#equalLevel).
+fn unify_level(tcs: TCS, left: &Level, right: &Level) -> TCM {
+    let a = level_view(&tcs, left.clone())?;
+    let b = level_view(&tcs, right.clone())?;
+    if a.leq(&b) && b.leq(&a) {
+        return Ok(tcs);
+    }
+    for (meta, other) in [(&a, &b), (&b, &a)].iter() {
+        let (k, mi, elims) = match meta {
+            Level::Max(0, atoms) => match atoms.as_slice() {
+                [(k, Term::Whnf(Val::Meta(mi, elims)))] if is_unsolved(&tcs, *mi) => {
+                    (*k, *mi, elims)
+                }
+                _ => continue,
+            },
+            _ => continue,
+        };
+        if let Some(sol) = (*other).clone().fall(k) {
+            return unify_meta_with(tcs, &Term::level(sol), mi, elims);
+        }
+    }
+    let blocker = unsolved_meta_atom(&tcs, &a).or_else(|| unsolved_meta_atom(&tcs, &b));
+    let (a, b) = (Term::level(a), Term::level(b));
+    match blocker {
+        Some(mi) => postpone(tcs, Comparison::Eq, &a, &b, mi),
+        None => Err(TCE::different_term(a, b)),
+    }
+}
+
 #[allow(clippy::many_single_char_names)]
 fn unify_val(mut tcs: TCS, left: &Val, right: &Val) -> TCM {
     use Val::*;
     match (left, right) {
-        (Type(sub_l), Type(sup_l)) if sub_l == sup_l => Ok(tcs),
+        (Type(a), Type(b)) | (Level(a), Level(b)) => unify_level(tcs, a, b),
+        (LevelType, LevelType) => Ok(tcs),
+        (Level(a), v @ Var(..)) | (v @ Var(..), Level(a)) => unify_level(
+            tcs,
+            a,
+            &crate::syntax::core::Level::atom(Term::Whnf(v.clone())),
+        ),
         (Data(left), Data(right)) if left.kind == right.kind => Unify::unify(tcs, left, right),
         (Pi(a, c0), Pi(b, c1)) if a.licit == b.licit => {
             tcs = Unify::unify(tcs, &a.ty, &b.ty)?;
             Unify::unify(tcs, c0, c1)
         }
-        (Cons(c0, a), Cons(c1, b)) if c0.name == c1.name => {
+        // The parameter types are not compared, because they're
+        // supposed to be well-typed under the same type.
+        (Lam(a, c0), Lam(b, c1)) if a.licit == b.licit => Unify::unify(tcs, c0, c1),
+        (Cons(c0, a), Cons(c1, b)) if c0.cons_ix == c1.cons_ix => {
             Unify::unify(tcs, a.as_slice(), b.as_slice())
         }
+        // Eta for functions, `f` is `\x. f x`.
+        (Lam(_, Closure::Plain(body)), f) | (f, Lam(_, Closure::Plain(body)))
+            if !matches!(f, Meta(..)) =>
+        {
+            let f = Term::Whnf(f.clone()).reduce_dbi(Subst::raise(DBI(1)));
+            let f = f.apply(vec![Term::from_dbi(DBI(0))]);
+            compare_closure(
+                tcs,
+                &Closure::plain(*body.clone()),
+                &Closure::plain(f),
+                Unify::unify,
+            )
+        }
+        (Cons(c, args), r @ Var(..)) | (r @ Var(..), Cons(c, args)) if is_eta_record(&tcs, c) => {
+            unify_record_eta(tcs, c, args, &Term::Whnf(r.clone()))
+        }
         (Axiom(i), Axiom(j)) if i == j => Ok(tcs),
         (Meta(i, a), Meta(j, b)) => {
             if i == j {
                 Unify::unify(tcs, a.as_slice(), b.as_slice())
-            } else if a.is_empty() {
-                unify_meta_with(tcs, right, *i)
-            } else if b.is_empty() {
-                unify_meta_with(tcs, left, *j)
+            } else if is_solvable(&tcs, *i, a) {
+                unify_meta_with(tcs, &Term::Whnf(right.clone()), *i, a)
             } else {
-                unimplemented!()
+                unify_meta_with(tcs, &Term::Whnf(left.clone()), *j, b)
             }
         }
-        (Meta(i, a), b) | (b, Meta(i, a)) if a.is_empty() => unify_meta_with(tcs, b, *i),
+        (Meta(i, a), b) | (b, Meta(i, a)) => unify_meta_with(tcs, &Term::Whnf(b.clone()), *i, a),
         (Var(i, a), Var(j, b)) if i == j => Unify::unify(tcs, a.as_slice(), b.as_slice()),
         (Id(a, b, c), Id(x, y, z)) => {
             tcs = Unify::unify(tcs, a, x)?;
             tcs = Unify::unify(tcs, b, y)?;
             Unify::unify(tcs, c, z)
         }
-        // Uniqueness of identity proof??
+        // Both are the constructor of the identity type, this is not UIP.
         (Refl, Refl) => Ok(tcs),
         (a, b) => Err(TCE::different_term(
             Term::Whnf(a.clone()),
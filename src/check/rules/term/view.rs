@@ -2,10 +2,13 @@ use voile_util::uid::DBI;
 
 use crate::{
     check::{
-        monad::{TCE, TCMS, TCS},
-        rules::simplify,
+        monad::{TCE, TCM, TCMS, TCS},
+        rules::{simplify, simplify_ref},
+    },
+    syntax::core::{
+        subst::{DeBruijn, RedEx, Subst},
+        Closure, Decl, Elim, Term, Val, ValData,
     },
-    syntax::core::{Term, Val, ValData},
 };
 
 /// Is it a datatype or a record type?
@@ -17,7 +20,7 @@ pub fn expect_data(tcs: TCS, term: Term) -> TCMS<ValData> {
     }
 }
 
-/// A borrowing version of [`is_eta_var`](Self::is_eta_var).
+/// A borrowing version of [`is_eta_var`](self::is_eta_var).
 pub fn is_eta_var_ref(tcs: TCS, term: &Term, ty: &Term) -> TCMS<Option<DBI>> {
     match term {
         Term::Whnf(Val::Var(dbi, v)) if v.is_empty() => Ok((Some(*dbi), tcs)),
@@ -29,13 +32,69 @@ pub fn is_eta_var_ref(tcs: TCS, term: &Term, ty: &Term) -> TCMS<Option<DBI>> {
 /// to a variable. Returns just the de Bruijn-index of the variable if it is,
 /// or nothing otherwise.
 /// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Records.html#isEtaVar).
-///
-/// TODO: type-directedness.
 pub fn is_eta_var(tcs: TCS, term: Term, ty: Term) -> TCMS<Option<DBI>> {
-    let (term, tcs) = simplify(tcs, term)?;
-    let (ty, tcs) = simplify(tcs, ty)?;
-    match (term, ty) {
-        (Val::Var(dbi, v), _) if v.is_empty() => Ok((Some(dbi), tcs)),
-        _ => unimplemented!(),
+    let var = eta_var(&tcs, term, ty, None, vec![])?;
+    Ok((var, tcs))
+}
+
+/// `term` should be the variable `var` (if known) eliminated by `elims`.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.Records.html#isEtaVarG).
+fn eta_var(
+    tcs: &TCS,
+    term: Term,
+    ty: Term,
+    var: Option<DBI>,
+    elims: Vec<Elim>,
+) -> TCM<Option<DBI>> {
+    let whnf = |term| match simplify_ref(tcs, term) {
+        Ok(val) => Ok(Some(val)),
+        Err(TCE::Blocked(..)) => Ok(None),
+        Err(e) => Err(e),
+    };
+    let term = match whnf(term)? {
+        Some(Val::Var(i, es)) => {
+            let matched = var.unwrap_or(i) == i && es == elims;
+            return Ok(if matched { Some(i) } else { None });
+        }
+        Some(val) => Term::Whnf(val),
+        None => return Ok(None),
+    };
+    match whnf(ty)? {
+        Some(Val::Data(data)) => {
+            let mut projs = match tcs.def(data.def) {
+                Decl::Codata(info) if info.eta => (info.fields.iter())
+                    .map(|(name, ix)| (*ix, name.clone()))
+                    .collect::<Vec<_>>(),
+                _ => return Ok(None),
+            };
+            projs.sort();
+            let mut found = var;
+            for (ix, name) in projs {
+                let proj_ty = match tcs.def(ix) {
+                    Decl::Proj(proj) => proj.instantiate(data.args.clone(), term.clone()),
+                    _ => unreachable!(),
+                };
+                let proj = vec![Elim::Proj(name)];
+                let mut proj_elims = elims.clone();
+                proj_elims.extend(proj.clone());
+                let field = term.clone().apply_elim(proj);
+                match eta_var(tcs, field, proj_ty, found, proj_elims)? {
+                    Some(i) => found = Some(i),
+                    None => return Ok(None),
+                }
+            }
+            Ok(found)
+        }
+        Some(Val::Pi(_, Closure::Plain(ret))) => {
+            let raise = Subst::raise(DBI(1));
+            let x = Term::from_dbi(DBI(0));
+            let term = term.reduce_dbi(raise.clone()).apply(vec![x.clone()]);
+            let var = var.map(|DBI(i)| DBI(i + 1));
+            let mut elims = elims.reduce_dbi(raise);
+            elims.push(Elim::app(x));
+            let i = eta_var(tcs, term, *ret, var, elims)?;
+            Ok(i.and_then(|DBI(i)| i.checked_sub(1).map(DBI)))
+        }
+        _ => Ok(None),
     }
 }
@@ -1,43 +1,106 @@
-use voile_util::{loc::Ident, uid::GI};
+use voile_util::{
+    loc::Ident,
+    tags::Plicit,
+    uid::{DBI, GI},
+};
 
 use crate::{
     check::{
         monad::{ValTCM, TCE, TCM, TCS},
-        pats::{build_subst, match_copats, Blocked, Match, RedM, Stuck},
+        pats::{build_subst, match_copats, Blocked, Match, RedM, Simpl, Stuck},
+        rules::term::level::instantiate,
     },
     syntax::{
-        common::{ConHead, Ductive},
-        core::{subst::RedEx, Clause, Decl, Elim, Term, Val},
+        common::ConHead,
+        core::{
+            subst::{RedEx, Subst},
+            Bind, CaseTree, Clause, Closure, Decl, Elim, TeleS, Term, Val,
+        },
     },
 };
 
 pub fn simplify(tcs: TCS, term: Term) -> ValTCM {
+    let val = simplify_ref(&tcs, term)?;
+    Ok((val, tcs))
+}
+
+/// A borrowing version of [`simplify`](self::simplify),
+/// used by pattern matching.
+pub fn simplify_ref(tcs: &TCS, term: Term) -> TCM<Val> {
     match term {
-        Term::Whnf(whnf) => Ok((whnf, tcs)),
+        Term::Whnf(Val::Meta(mi, elims)) => match instantiate(tcs, Term::meta(mi, elims)) {
+            Term::Whnf(Val::Meta(mi, elims)) => Ok(Val::Meta(mi, elims)),
+            solution => simplify_ref(tcs, solution),
+        },
+        Term::Whnf(whnf) => Ok(whnf),
         Term::Redex(def, id, elims) => match tcs.def(def) {
-            Decl::Data(_) => Ok((Val::inductive(def, elims_to_terms(elims)?), tcs)),
-            Decl::Codata(_) => Ok((Val::coinductive(def, elims_to_terms(elims)?), tcs)),
+            Decl::Data(_) => Ok(Val::inductive(def, elims_to_terms(elims)?)),
+            Decl::Codata(_) => Ok(Val::coinductive(def, elims_to_terms(elims)?)),
             Decl::Cons(cons) => {
-                let (ductive, fields) = match &cons.fields {
-                    Some(fields) => (Ductive::In, fields.clone()),
-                    None => (Ductive::Coin, vec![]),
+                let mut elims = elims;
+                let app_len = elims.iter().take_while(|elim| !elim.is_proj()).count();
+                let projs = elims.split_off(app_len);
+                let params = match tcs.def(cons.data) {
+                    Decl::Data(info) => &info.params,
+                    Decl::Codata(info) => &info.params,
+                    _ => unreachable!(),
+                };
+                let head = ConHead {
+                    name: id,
+                    ..cons.head(def)
                 };
-                let head = ConHead::new(id, cons.data, ductive, fields);
-                Ok((Val::Cons(head, elims_to_terms(elims)?), tcs))
+                let mut args = elims_to_terms(elims)?;
+                if args.len() < params.len() {
+                    // Still waiting for some data parameters.
+                    debug_assert!(projs.is_empty());
+                    return Ok(params_lam(params, args, Val::Cons(head, vec![])));
+                }
+                // Like Agda, we don't store the data parameters in constructors.
+                let args = args.split_off(params.len());
+                let cons = Val::Cons(head, args);
+                if projs.is_empty() {
+                    Ok(cons)
+                } else {
+                    // Projecting a record constructor.
+                    simplify_ref(tcs, Term::Whnf(cons).apply_elim(projs))
+                }
+            }
+            Decl::Proj(proj) => {
+                let params_len = match tcs.def(proj.codata) {
+                    Decl::Codata(info) => info.params.len(),
+                    _ => unreachable!(),
+                };
+                // Prefix projection, the record comes after the codata parameters.
+                if elims.len() <= params_len {
+                    let term = Term::Redex(def, id, elims);
+                    return Err(TCE::blocked(Blocked::new(Stuck::UnderApplied, term)));
+                }
+                let mut elims = elims;
+                let mut rest = elims.split_off(params_len);
+                let record = rest.remove(0).try_into_app().map_err(TCE::NotTerm)?;
+                rest.insert(0, Elim::Proj(proj.name.text.clone()));
+                // Now it's a postfix projection.
+                simplify_ref(tcs, record.apply_elim(rest))
             }
-            Decl::Proj { .. } => unimplemented!(),
             Decl::Func(func) => {
-                let clauses = (func.clauses.iter())
-                    // Our elims should be enough
-                    .filter(|clause| elims.len() >= clause.patterns.len())
-                    // Should not be an absurd clause
-                    .filter(|clause| !clause.is_absurd())
-                    .cloned()
-                    .collect();
-                match unfold_func(&tcs, def, id, clauses, elims) {
-                    Ok((_, term)) => simplify(tcs, term),
+                let unfolded = match &func.case_tree {
+                    Some(tree) => unfold_case_tree(tcs, def, id, tree, elims)?,
+                    // The clauses are not yet coverage-checked, or defined by copatterns.
+                    None => {
+                        let clauses = (func.clauses.iter())
+                            // Our elims should be enough
+                            .filter(|clause| elims.len() >= clause.patterns.len())
+                            // Should not be an absurd clause
+                            .filter(|clause| !clause.is_absurd())
+                            .cloned()
+                            .collect();
+                        unfold_func(tcs, def, id, clauses, elims)?
+                    }
+                };
+                match unfolded {
+                    Ok((_, term)) => simplify_ref(tcs, term),
                     Err(blockage) => match blockage.stuck {
-                        Stuck::NotBlocked => simplify(tcs, blockage.anyway),
+                        Stuck::NotBlocked => simplify_ref(tcs, blockage.anyway),
                         _ => Err(TCE::blocked(blockage)),
                     },
                 }
@@ -54,31 +117,31 @@ pub fn unfold_func(
     func_name: Ident,
     clauses: Vec<Clause>,
     mut elims: Vec<Elim>,
-) -> RedM<Term, Blocked<Term>> {
+) -> TCM<RedM<Term, Blocked<Term>>> {
     for clause in clauses {
         let mut es = elims;
         let pat_len = clause.patterns.len();
         let mut rest = es.split_off(pat_len);
         let copats = clause.patterns.into_iter().zip(es.into_iter());
-        let (m, es) = match_copats(tcs, copats);
+        let (m, es) = match_copats(tcs, copats)?;
         match m {
             Match::Yes(s, vs) => {
-                let subst = build_subst(vs, pat_len);
+                let subst = build_subst(vs, clause.pat_tele.len());
                 let body = match clause.body {
                     None => {
                         elims = es;
                         elims.append(&mut rest);
                         let term = Term::def(def, func_name, elims);
-                        return Err(Blocked::new(Stuck::AbsurdMatch, term));
+                        return Ok(Err(Blocked::new(Stuck::AbsurdMatch, term)));
                     }
                     Some(body) => body,
                 };
-                return Ok((s, body.reduce_dbi(subst).apply_elim(rest)));
+                return Ok(Ok((s, body.reduce_dbi(subst).apply_elim(rest))));
             }
             Match::Dunno(b) => {
                 elims = es;
                 elims.append(&mut rest);
-                return Err(b.map_anyway(|()| Term::def(def, func_name, elims)));
+                return Ok(Err(b.map_anyway(|()| Term::def(def, func_name, elims))));
             }
             // continue to next clause
             Match::No => {
@@ -88,7 +151,84 @@ pub fn unfold_func(
         }
     }
     let term = Term::def(def, func_name, elims);
-    Err(Blocked::new(Stuck::MissingClauses, term))
+    Ok(Err(Blocked::new(Stuck::MissingClauses, term)))
+}
+
+/// Walk the case tree to unfold the declaration.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.CompiledClause.Match.html#match%27).
+pub fn unfold_case_tree(
+    tcs: &TCS,
+    def: GI,
+    func_name: Ident,
+    mut tree: &CaseTree,
+    elims: Vec<Elim>,
+) -> TCM<RedM<Term, Blocked<Term>>> {
+    let app_len = elims.iter().take_while(|elim| !elim.is_proj()).count();
+    let mut stack = (elims[..app_len].iter().cloned())
+        .map(Elim::into_app)
+        .collect::<Vec<_>>();
+    let stuck = |stuck| {
+        let term = Term::def(def, func_name.clone(), elims.clone());
+        Ok(Err(Blocked::new(stuck, term)))
+    };
+    loop {
+        let pos = match tree {
+            CaseTree::Case(pos, ..) | CaseTree::Refl(pos, ..) => *pos,
+            CaseTree::Done(len, _) if stack.len() < *len => return stuck(Stuck::UnderApplied),
+            CaseTree::Done(len, body) => {
+                let rest = stack.split_off(*len);
+                let subst = Subst::parallel(stack.into_iter().rev());
+                let rest = (rest.into_iter().map(Elim::app))
+                    .chain(elims[app_len..].iter().cloned())
+                    .collect();
+                return Ok(Ok((
+                    Simpl::Yes,
+                    body.clone().reduce_dbi(subst).apply_elim(rest),
+                )));
+            }
+            CaseTree::Fail => return stuck(Stuck::AbsurdMatch),
+        };
+        if pos >= stack.len() {
+            return stuck(Stuck::UnderApplied);
+        }
+        let val = match simplify_ref(tcs, stack[pos].clone()) {
+            Ok(val) => val,
+            Err(TCE::Blocked(b)) => return stuck(b.stuck),
+            Err(e) => return Err(e),
+        };
+        match (tree, val) {
+            (CaseTree::Case(_, branches), Val::Cons(con, args)) => {
+                let branch = branches.iter().find(|(ix, _)| *ix == con.cons_ix);
+                match branch {
+                    Some((_, branch)) => tree = branch,
+                    None => return stuck(Stuck::MissingClauses),
+                }
+                stack.splice(pos..=pos, args);
+            }
+            (CaseTree::Refl(_, branch), Val::Refl) => {
+                tree = &**branch;
+                stack.remove(pos);
+            }
+            (_, Val::Meta(mi, ..)) => return stuck(Stuck::OnMeta(mi)),
+            (_, val) => return stuck(Stuck::OnElim(Elim::app(Term::Whnf(val)))),
+        }
+    }
+}
+
+/// `\{p_m} .. {p_n}. cons` for a constructor applied to the first `m`
+/// data parameters `args`, since the parameters are not stored in `cons`.
+fn params_lam(params: &TeleS, args: Vec<Term>, cons: Val) -> Val {
+    let m = args.len();
+    let args = Subst::parallel(args.into_iter().rev());
+    let binds = (params.iter().enumerate().skip(m))
+        .map(|(k, bind)| {
+            let ty = bind.ty.clone().reduce_dbi(args.clone().lift_by(DBI(k - m)));
+            Bind::new(Plicit::Im, bind.name, ty)
+        })
+        .collect::<Vec<_>>();
+    binds.into_iter().rfold(cons, |body, bind| {
+        Val::Lam(bind.boxed(), Closure::plain(Term::Whnf(body)))
+    })
 }
 
 fn elims_to_terms(elims: Vec<Elim>) -> TCM<Vec<Term>> {
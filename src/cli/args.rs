@@ -21,6 +21,10 @@ pub struct CliOptions {
     #[structopt(long)]
     pub indent_size: Option<usize>,
 
+    /// Disable the K axiom (uniqueness of identity proofs) in pattern matching.
+    #[structopt(long = "without-K")]
+    pub without_k: bool,
+
     /// Parses but do not type-check the input file
     #[structopt(short = "p", long)]
     pub parse_only: bool,
@@ -35,11 +35,15 @@ fn main_file(args: CliOptions) -> Option<(TCS, DesugarState)> {
     let mut tcs = TCS::considerate_of(&abs_decls);
     tcs.indentation_size(indentation);
     tcs.trace_tc = args.trace;
+    tcs.without_k = args.without_k;
     let checked = check_decls(tcs, abs_decls.decls.clone()).unwrap_or_else(|err| {
         eprintln!("{}", err);
         eprintln!("\u{1F528}");
         std::process::exit(1)
     });
+    for warning in &checked.warnings {
+        eprintln!("{}", warning);
+    }
 
     success(quiet);
     Some((checked, abs_decls))
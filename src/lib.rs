@@ -22,10 +22,11 @@ I want this language to have:
 + Conversion check should be nominal for simplicity
 + Support case-tree and pattern instantiation
 + Surface syntax should be considerate of parsing ease
-+ Simple (co)inductive types (not indexed) with an identity type as
-  described in Jesper's paper
-  + It's not actually (co)inductive --
-    there's no termination or productivity checks yet
++ Inductive families (indexed datatypes) and coinductive records with an
+  identity type as described in Jesper's paper, with termination and
+  guardedness checks
++ Universe polymorphism with level variables
++ Eta conversion for functions and records
 + Definition by pattern matching according to Jesper's paper
 + Coverage check + case-tree generation described in Jesper's paper
 + Prefix (applying on projection) *and* postfix (projecting from data)
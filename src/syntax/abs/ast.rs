@@ -16,9 +16,24 @@ pub enum Abs {
     Meta(Ident, MI),
     App(Box<Self>, Box<Vec1<Self>>),
     Pi(Loc, Bind<Box<Self>>, Box<Self>),
+    /// Lambda abstraction, the parameter is explicit and unannotated.
+    Lam(Ident, UID, Box<Self>),
     Type(Ident, Level),
+    /// Universe at a level expression plus a constant, like `Type l`.
+    Universe(Ident, Level, Box<Self>),
+    /// The type of universe levels.
+    Level(Ident),
+    /// Successor of universe levels.
+    LSuc(Ident),
+    /// Maximum of universe levels.
+    LMax(Ident),
     Cons(Ident, GI),
     Proj(Ident, GI),
+    /// Implicit argument, with the name of the parameter if given.
+    Implicit(Option<Ident>, Box<Self>),
+    /// Identity type, the type of the endpoints is inferred.
+    Id(Box<Self>, Box<Self>),
+    Refl(Ident),
 }
 
 /// Application's internal view.
@@ -65,6 +80,18 @@ impl Abs {
         Abs::App(Box::new(f), Box::new(args))
     }
 
+    pub fn lam(param: Ident, name: UID, body: Self) -> Self {
+        Abs::Lam(param, name, Box::new(body))
+    }
+
+    pub fn implicit(name: Option<Ident>, arg: Self) -> Self {
+        Abs::Implicit(name, Box::new(arg))
+    }
+
+    pub fn id(a: Self, b: Self) -> Self {
+        Abs::Id(Box::new(a), Box::new(b))
+    }
+
     pub fn universe(id: Ident) -> Self {
         Abs::universe_at(id, Default::default())
     }
@@ -85,11 +112,20 @@ impl ToLoc for Abs {
             Proj(ident, ..)
             | Cons(ident, ..)
             | Type(ident, ..)
+            | Level(ident)
+            | LSuc(ident)
+            | LMax(ident)
             | Def(ident, ..)
             | Var(ident, ..)
+            | Refl(ident)
             | Meta(ident, ..) => ident.loc,
             Pi(loc, ..) => *loc,
+            Universe(ident, _, l) => ident.loc + l.loc(),
+            Lam(param, _, body) => param.loc + body.loc(),
+            Implicit(Some(name), arg) => name.loc + arg.loc(),
+            Implicit(None, arg) => arg.loc(),
             App(f, a) => f.loc() + a.last().loc(),
+            Id(a, b) => a.loc() + b.loc(),
         }
     }
 }
@@ -1,7 +1,6 @@
 use voile_util::{
-    level::Level,
     loc::{Ident, Loc, ToLoc},
-    uid::GI,
+    uid::{GI, UID},
 };
 
 use crate::syntax::abs::{Abs, AbsCopat, AbsTele};
@@ -11,6 +10,8 @@ pub struct AbsConsInfo {
     pub source: Loc,
     pub name: Ident,
     pub tele: AbsTele,
+    /// The return type, required if the datatype has indices.
+    pub ret: Option<Abs>,
     /// Corresponding datatype's index.
     pub data_ix: GI,
 }
@@ -34,10 +35,14 @@ pub struct AbsProjInfo {
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct AbsCodataInfo {
     pub source: Loc,
-    pub self_ref: Option<Ident>,
+    /// The record value referred in the projection types, named `self`.
+    pub self_ref: Option<(Ident, UID)>,
     pub name: Ident,
     pub fields: Vec<GI>,
-    pub level: Level,
+    /// The record constructor, which comes after the projections.
+    pub cons: Option<GI>,
+    /// The universe it lives in, `Type` if not specified.
+    pub universe: Option<Abs>,
     pub tele: AbsTele,
 }
 
@@ -45,7 +50,9 @@ pub struct AbsCodataInfo {
 pub struct AbsDataInfo {
     pub source: Loc,
     pub name: Ident,
-    pub level: Level,
+    /// The types of its indices and the universe it lives in,
+    /// `Type` if not specified.
+    pub ty: Option<Abs>,
     pub tele: AbsTele,
     pub conses: Vec<GI>,
 }
@@ -107,6 +114,6 @@ pub struct AbsClause {
     pub patterns: Vec<AbsCopat>,
     /// Index of the type signature definition.
     pub definition: GI,
-    /// Rhs.
-    pub body: Abs,
+    /// Rhs, absent in absurd clauses.
+    pub body: Option<Abs>,
 }
@@ -1,13 +1,17 @@
-use voile_util::{level::Level, loc::*, uid::GI};
+use voile_util::{
+    loc::*,
+    uid::{GI, UID},
+};
 
 use crate::syntax::abs::*;
 
 impl AbsConsInfo {
-    pub fn new(source: Loc, name: Ident, tele: AbsTele, data_index: GI) -> Self {
+    pub fn new(source: Loc, name: Ident, tele: AbsTele, ret: Option<Abs>, data_index: GI) -> Self {
         Self {
             source,
             name,
             tele,
+            ret,
             data_ix: data_index,
         }
     }
@@ -25,11 +29,11 @@ impl AbsProjInfo {
 }
 
 impl AbsDataInfo {
-    pub fn new(source: Loc, name: Ident, level: Level, tele: AbsTele, conses: Vec<GI>) -> Self {
+    pub fn new(source: Loc, name: Ident, ty: Option<Abs>, tele: AbsTele, conses: Vec<GI>) -> Self {
         AbsDataInfo {
             source,
             name,
-            level,
+            ty,
             tele,
             conses,
         }
@@ -42,7 +46,7 @@ impl AbsClause {
         name: Ident,
         patterns: Vec<AbsCopat>,
         definition: GI,
-        body: Abs,
+        body: Option<Abs>,
     ) -> Self {
         Self {
             source,
@@ -64,18 +68,20 @@ impl AbsCodataInfo {
     pub fn new(
         source: Loc,
         name: Ident,
-        me: Option<Ident>,
-        level: Level,
+        me: Option<(Ident, UID)>,
+        universe: Option<Abs>,
         tele: AbsTele,
         fields: Vec<GI>,
+        cons: Option<GI>,
     ) -> Self {
         Self {
             source,
             name,
             self_ref: me,
-            level,
+            universe,
             tele,
             fields,
+            cons,
         }
     }
 }
@@ -10,7 +10,7 @@ use crate::syntax::{
         Abs, AbsClause, AbsCodataInfo, AbsConsInfo, AbsDataInfo, AbsDecl, AbsDefnInfo, AbsPat,
         AbsProjInfo, AbsTele, Bind,
     },
-    common::Ductive,
+    common::{ConHead, Ductive},
     pat::{Copat, Pat},
     surf::{Expr, ExprCopat, ExprDecl, ExprPat, NamedTele, Param},
 };
@@ -40,6 +40,7 @@ pub fn desugar_params(mut state: DesugarState, params: Vec<Param>) -> DeclM<AbsT
         state = new_state;
         let mut intros = |name: Ident, licit: Plicit, ty: Abs| {
             let uid = unsafe { next_uid() };
+            state.names.insert(uid, name.text.clone());
             state.local.insert(name.text, uid);
             tele.push(Bind::new(licit, uid, ty));
         };
@@ -66,9 +67,19 @@ pub fn desugar_patterns(state: DesugarState, pats: Vec<ExprPat>) -> DeclM<Vec<Ab
 
 pub fn desugar_pattern(state: DesugarState, pat: ExprPat) -> DeclM<AbsPat> {
     match pat {
+        // Like Agda, a name referring to a constructor is a constructor pattern.
+        Pat::Var(name)
+            if matches!(
+                state.lookup_by_name(&name.text),
+                Some((_, AbsDecl::Cons(..)))
+            ) =>
+        {
+            desugar_pattern(state, Pat::Cons(false, ConHead::pseudo(name), vec![]))
+        }
         Pat::Var(name) => {
             let mut st = state;
             let uid = unsafe { next_uid() };
+            st.names.insert(uid, name.text.clone());
             st.local.insert(name.text, uid);
             Ok((Pat::Var(uid), st))
         }
@@ -100,7 +111,7 @@ pub fn desugar_clause(
     defn_ix: GI,
     name: Ident,
     pats: Vec<ExprCopat>,
-    body: Expr,
+    body: Option<Expr>,
 ) -> DesugarM {
     let mut abs_pats = Vec::with_capacity(pats.len());
     for copat in pats {
@@ -110,13 +121,27 @@ pub fn desugar_clause(
                 state = st;
                 Copat::App(pat)
             }
+            Copat::Implicit(name, app) => {
+                let (pat, st) = desugar_pattern(state, app)?;
+                state = st;
+                Copat::Implicit(name, pat)
+            }
             Copat::Proj(s) => Copat::Proj(s),
         };
         abs_pats.push(pat);
     }
     // Now `state` has been filled with local variable bindings!
-    let (body, mut state) = desugar_expr(state, body)?;
-    let loc = name.loc + body.loc();
+    let (body, mut state) = match body {
+        Some(body) => {
+            let (body, state) = desugar_expr(state, body)?;
+            (Some(body), state)
+        }
+        None => (None, state),
+    };
+    let loc = match &body {
+        Some(body) => name.loc + body.loc(),
+        None => name.loc,
+    };
     let info = AbsClause::new(loc, name, abs_pats, defn_ix, body);
     state.decls.push(AbsDecl::Clause(info));
     state.local.clear();
@@ -135,20 +160,22 @@ pub fn desugar_decl(state: DesugarState, decl: ExprDecl) -> DesugarM {
         }
         Cls(name, pats, body) => match state.lookup_by_name(&name.text) {
             Some((ix, AbsDecl::Defn { .. })) => desugar_clause(state, ix, name, pats, body),
+            // The signature is left to inference.
             None => {
                 let mut state = state;
                 let meta = Abs::Meta(name.clone(), state.fresh_meta());
                 let decl_len = state.decl_len();
-                let mut state = desugar_clause(state, decl_len, name.clone(), pats, body)?;
-                state.ensure_local_emptiness();
-                let defn = AbsDecl::Defn(AbsDefnInfo::new(name.loc, name, meta));
+                let defn = AbsDecl::Defn(AbsDefnInfo::new(name.loc, name.clone(), meta));
                 state.decls.push(defn);
+                let state = desugar_clause(state, decl_len, name, pats, body)?;
+                state.ensure_local_emptiness();
                 Ok(state)
             }
             Some((_, other)) => Err(DesugarErr::NotDefn(other.decl_name().clone())),
         },
-        Data(signature, conses) => {
-            let (name, tele, mut state) = desugar_telescope(state, signature)?;
+        Data(signature, universe, conses) => {
+            let (name, tele, state) = desugar_telescope(state, signature)?;
+            let (ty, mut state) = desugar_expr_opt(state, universe)?;
             state.decls.reserve(conses.len());
             let loc = match tele.last() {
                 None => name.loc,
@@ -156,33 +183,45 @@ pub fn desugar_decl(state: DesugarState, decl: ExprDecl) -> DesugarM {
             };
             let data_ix = state.decls.len();
             let cons_ices = ops_range(data_ix + 1, conses.len());
-            let data = AbsDataInfo::new(loc, name, Default::default(), tele, cons_ices);
+            let data = AbsDataInfo::new(loc, name, ty, tele, cons_ices);
             let data = AbsDecl::Data(data);
             state.decls.push(data);
             for cons in conses {
                 let (binds, new_st) = desugar_params(state, cons.tele)?;
+                let (ret, new_st) = desugar_expr_opt(new_st, cons.ret)?;
                 state = new_st;
                 let name = cons.name;
                 let loc = match binds.first() {
                     None => name.loc,
                     Some(a) => name.loc + a.ty.loc(),
                 };
-                let cons = AbsDecl::Cons(AbsConsInfo::new(loc, name, binds, GI(data_ix)));
+                let info = AbsConsInfo::new(loc, name, binds, ret, GI(data_ix));
+                let cons = AbsDecl::Cons(info);
                 state.decls.push(cons);
             }
             Ok(state)
         }
-        Codata(signature, fields) => {
-            let (name, tele, mut state) = desugar_telescope(state, signature)?;
-            state.decls.reserve(fields.len());
+        Codata(signature, universe, cons, fields) => {
+            let (name, tele, state) = desugar_telescope(state, signature)?;
+            let (universe, mut state) = desugar_expr_opt(state, universe)?;
+            state.decls.reserve(fields.len() + 1);
             let loc = match tele.last() {
                 None => name.loc,
                 Some(loc) => name.loc + loc.ty.loc(),
             };
             let codata_ix = state.decls.len();
             let fields_ices = ops_range(codata_ix + 1, fields.len());
-            // TODO: self reference?
-            let codata = AbsCodataInfo::new(loc, name, None, Default::default(), tele, fields_ices);
+            let cons_ix = cons.as_ref().map(|_| GI(codata_ix + 1 + fields.len()));
+            // Projection types may refer to the record value as `self`.
+            let me = Ident {
+                loc: name.loc,
+                text: "self".to_owned(),
+            };
+            let self_uid = unsafe { next_uid() };
+            let shadowed = state.local.insert(me.text.clone(), self_uid);
+            let self_ref = Some((me, self_uid));
+            let codata =
+                AbsCodataInfo::new(loc, name, self_ref, universe, tele, fields_ices, cons_ix);
             let codata = AbsDecl::Codata(codata);
             state.decls.push(codata);
             for field in fields {
@@ -193,11 +232,27 @@ pub fn desugar_decl(state: DesugarState, decl: ExprDecl) -> DesugarM {
                 let proj = AbsDecl::Proj(AbsProjInfo::new(loc, name, abs, GI(codata_ix)));
                 state.decls.push(proj);
             }
+            // Its parameters are the fields, known after the projections are checked.
+            if let Some(name) = cons {
+                let info = AbsConsInfo::new(name.loc, name, vec![], None, GI(codata_ix));
+                state.decls.push(AbsDecl::Cons(info));
+            }
+            match shadowed {
+                Some(uid) => state.local.insert("self".to_owned(), uid),
+                None => state.local.remove("self"),
+            };
             Ok(state)
         }
     }
 }
 
+fn desugar_expr_opt(state: DesugarState, expr: Option<Expr>) -> DeclM<Option<Abs>> {
+    match expr {
+        Some(expr) => desugar_expr(state, expr).map(|(abs, state)| (Some(abs), state)),
+        None => Ok((None, state)),
+    }
+}
+
 fn ops_range(start: usize, duration: usize) -> Vec<GI> {
     let cons_ices: Vec<_> = (start..start + duration).collect();
     cons_ices.into_iter().map(GI).collect()
@@ -1,4 +1,4 @@
-use voile_util::loc::ToLoc;
+use voile_util::{loc::ToLoc, uid::next_uid, vec1::Vec1};
 
 use crate::syntax::{
     abs::{
@@ -27,12 +27,25 @@ pub fn desugar_expr(state: DesugarState, expr: Expr) -> DesugarM<(Abs, DesugarSt
                 Err(DesugarErr::UnresolvedReference(v.clone()))
             }
         }
-        Expr::Type(i) => Ok((Abs::universe(i), state)),
+        Expr::Type(i, level) => Ok((Abs::universe_at(i, level), state)),
+        Expr::Refl(i) => Ok((Abs::Refl(i), state)),
+        Expr::Level(i) => Ok((Abs::Level(i), state)),
+        Expr::LSuc(i) => Ok((Abs::LSuc(i), state)),
+        Expr::LMax(i) => Ok((Abs::LMax(i), state)),
+        Expr::Id(a, b) => {
+            let (a, state) = desugar_expr(state, *a)?;
+            let (b, state) = desugar_expr(state, *b)?;
+            Ok((Abs::id(a, b), state))
+        }
         Expr::Meta(i) => {
             let mut state = state;
             let meta = Abs::meta(i, state.fresh_meta());
             Ok((meta, state))
         }
+        Expr::Implicit(name, arg) => {
+            let (arg, state) = desugar_expr(state, *arg)?;
+            Ok((Abs::implicit(name, arg), state))
+        }
         Expr::Proj(i) => {
             // TODO: better error msg for resolved but non-proj
             if let Some((ix, AbsDecl::Proj { .. })) = state.lookup_by_name(&i.text) {
@@ -42,8 +55,19 @@ pub fn desugar_expr(state: DesugarState, expr: Expr) -> DesugarM<(Abs, DesugarSt
             }
         }
         Expr::App(head, tail) => {
-            let (head, state) = desugar_expr(state, *head)?;
             let (state, args) = tail.try_scan(state, desugar_expr)?;
+            // `Type l` is a universe at the level `l`.
+            if let Expr::Type(i, level) = *head {
+                let mut args = args.into_vec();
+                let universe = Abs::Universe(i, level, Box::new(args.remove(0)));
+                let abs = if args.is_empty() {
+                    universe
+                } else {
+                    Abs::app(universe, Vec1::new(args.remove(0), args))
+                };
+                return Ok((abs, state));
+            }
+            let (head, state) = desugar_expr(state, *head)?;
             Ok((Abs::app(head, args), state))
         }
         Expr::Pi(params, ret) => {
@@ -55,5 +79,27 @@ pub fn desugar_expr(state: DesugarState, expr: Expr) -> DesugarM<(Abs, DesugarSt
             });
             Ok((pi, state))
         }
+        Expr::Lam(params, body) => {
+            let mut state = state;
+            let params = params.into_vec();
+            let mut shadowed = Vec::with_capacity(params.len());
+            let mut binds = Vec::with_capacity(params.len());
+            for param in params {
+                let uid = unsafe { next_uid() };
+                shadowed.push(state.local.insert(param.text.clone(), uid));
+                binds.push((param, uid));
+            }
+            let (body, mut state) = desugar_expr(state, *body)?;
+            // Lambda parameters are only visible inside the lambda body.
+            for ((param, _), old) in binds.iter().zip(shadowed).rev() {
+                match old {
+                    Some(uid) => state.local.insert(param.text.clone(), uid),
+                    None => state.local.remove(&param.text),
+                };
+            }
+            let lam =
+                (binds.into_iter()).rfold(body, |body, (param, uid)| Abs::lam(param, uid, body));
+            Ok((lam, state))
+        }
     }
 }
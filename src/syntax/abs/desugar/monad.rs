@@ -14,6 +14,8 @@ pub type DesugarM<State = DesugarState> = Result<State, DesugarErr>;
 pub struct DesugarState {
     pub decls: Vec<AbsDecl>,
     pub local: BTreeMap<String, UID>,
+    /// Names of the parameters, kept for error messages.
+    pub names: BTreeMap<UID, String>,
     /// TODO: make it per-definition.
     pub meta_count: MI,
 }
@@ -23,6 +25,7 @@ impl DesugarState {
         Self {
             meta_count: Default::default(),
             local: Default::default(),
+            names: Default::default(),
             decls: Vec::with_capacity(decl_possible_size),
         }
     }
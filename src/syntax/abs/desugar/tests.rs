@@ -81,7 +81,7 @@ fn multiple_patterns_increases_ix() {
     assert_eq!(c.definition, GI(3));
     assert_eq!(
         expect_app_var_pat(c.patterns.remove(0)),
-        expect_var_expr(c.body)
+        expect_var_expr(c.body.unwrap())
     );
 }
 
@@ -99,7 +99,7 @@ fn simple_pattern_definition_desugar() {
     assert_eq!(c.patterns.len(), 1);
     assert_eq!(
         expect_app_var_pat(c.patterns.remove(0)),
-        expect_var_expr(c.body)
+        expect_var_expr(c.body.unwrap())
     );
 }
 
@@ -118,3 +118,40 @@ fn simple_definition_desugar() {
     assert_eq!(c.definition, GI(0));
     assert!(c.patterns.is_empty());
 }
+
+#[test]
+fn lambda_parameter_scope_desugar() {
+    let code = "\
+    definition test : Type -> Type;
+    clause test a = (\\a. a) a;
+    ";
+    let mut state = desugar_main(parse_str(code).unwrap()).unwrap();
+    println!("{:#?}", state);
+    assert!(state.local.is_empty());
+    let mut c = expect_clause(state.decls.remove(1));
+    let pat = expect_app_var_pat(c.patterns.remove(0));
+    let mut view = c.body.unwrap().into_app_view();
+    assert_eq!(view.args.len(), 1);
+    assert_eq!(expect_var_expr(view.args.remove(0)), pat);
+    match view.fun {
+        Abs::Lam(_, uid, body) => {
+            assert_ne!(uid, pat);
+            assert_eq!(expect_var_expr(*body), uid);
+        }
+        _ => panic!("Test failed"),
+    }
+}
+
+#[test]
+fn absurd_clause_desugar() {
+    let code = "\
+    data Void {};
+    definition test : Void -> Type;
+    clause test ();
+    ";
+    let mut state = desugar_main(parse_str(code).unwrap()).unwrap();
+    println!("{:#?}", state);
+    let c = expect_clause(state.decls.remove(2));
+    assert_eq!(c.patterns, vec![Copat::absurd()]);
+    assert_eq!(c.body, None);
+}
@@ -1,7 +1,6 @@
 use std::fmt::{Display, Error, Formatter};
 
 use voile_util::{tags::Plicit, uid::UID};
-use Plicit::{Ex as Explicit, Im as Implicit};
 
 use crate::syntax::abs::{Abs, Bind};
 
@@ -20,12 +19,22 @@ impl Display for Abs {
                 f.write_str(")")
             }
             Pi(_loc, Bind { licit, ty, .. }, clos) => match licit {
-                Explicit => write!(f, "({} -> {})", ty, clos),
-                Implicit => write!(f, "({{{}}} -> {})", ty, clos),
+                Plicit::Ex => write!(f, "({} -> {})", ty, clos),
+                Plicit::Im => write!(f, "({{{}}} -> {})", ty, clos),
             },
+            Lam(id, UID(uid), body) => write!(f, "(\\{}_{:?}. {})", id.text, uid, body),
             Type(_, l) => write!(f, "set{}", l),
+            Universe(_, voile_util::level::Level::Num(0), l) => write!(f, "set({})", l),
+            Universe(_, k, l) => write!(f, "set({} + {})", l, k),
+            Level(_) => f.write_str("Level"),
+            LSuc(_) => f.write_str("lsuc"),
+            LMax(_) => f.write_str("lmax"),
             Cons(id, _gi) => write!(f, "{}", id.text),
             Proj(id, _gi) => write!(f, "{}", id.text),
+            Implicit(Some(name), arg) => write!(f, "{{{} = {}}}", name.text, arg),
+            Implicit(None, arg) => write!(f, "{{{}}}", arg),
+            Id(a, b) => write!(f, "({} = {})", a, b),
+            Refl(_) => f.write_str("refl"),
         }
     }
 }
@@ -1,5 +1,4 @@
 use voile_util::{
-    level::Level,
     loc::Ident,
     meta::MI,
     tags::VarRec,
@@ -9,7 +8,10 @@ use voile_util::{
 use crate::syntax::{
     common,
     common::ConHead,
-    core::subst::{RedEx, Subst},
+    core::{
+        subst::{RedEx, Subst},
+        Level,
+    },
 };
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -24,10 +26,16 @@ pub struct ValData {
 pub enum Val {
     /// Type universe.
     Type(Level),
+    /// The type of universe levels.
+    LevelType,
+    /// Universe levels, as values of `LevelType`.
+    Level(Level),
     /// (Co)Data types, fully applied.
     Data(ValData),
     /// Pi-like types (dependent types), with parameter explicitly typed.
     Pi(Bind<Box<Term>>, Closure),
+    /// Lambda abstraction, with parameter explicitly typed.
+    Lam(Bind<Box<Term>>, Closure),
     /// Constructor invocation, fully applied.
     Cons(ConHead, Vec<Term>),
     /// Meta reference, with eliminations.
@@ -71,6 +79,8 @@ pub enum Closure {
 }
 
 impl Closure {
+    /// Substitute the bound variable with `arg`.
+    /// This is how beta-reduction is done.
     pub fn instantiate(self, arg: Term) -> Term {
         self.instantiate_safe(arg)
             .unwrap_or_else(|e| panic!("Cannot split on `{}`.", e))
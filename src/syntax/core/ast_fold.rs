@@ -1,4 +1,4 @@
-use crate::syntax::core::{Closure, Elim, Term, Val};
+use crate::syntax::core::{Closure, Elim, Level, Term, Val};
 
 pub trait FoldVal {
     fn try_fold_val<E, R>(
@@ -68,10 +68,24 @@ impl FoldVal for Val {
         match self {
             Cons(_, v) => v.try_fold_val(init, f),
             Data(i) => i.args.try_fold_val(init, f),
-            Axiom(..) | Type(..) | Refl => Ok(init),
-            Pi(p, clos) => clos.try_fold_val(p.ty.try_fold_val(init, f)?, f),
+            Type(l) | Level(l) => l.try_fold_val(init, f),
+            Axiom(..) | LevelType | Refl => Ok(init),
+            Pi(p, clos) | Lam(p, clos) => clos.try_fold_val(p.ty.try_fold_val(init, f)?, f),
             Id(a, b, c) => c.try_fold_val(b.try_fold_val(a.try_fold_val(init, f)?, f)?, f),
             Var(_, v) | Meta(_, v) => v.try_fold_val(init, f),
         }
     }
 }
+
+impl FoldVal for Level {
+    fn try_fold_val<E, R>(
+        &self,
+        init: R,
+        f: impl Fn(R, &Val) -> Result<R, E> + Copy,
+    ) -> Result<R, E> {
+        match self {
+            Level::Omega => Ok(init),
+            Level::Max(_, atoms) => (atoms.iter()).try_fold(init, |a, (_, t)| t.try_fold_val(a, f)),
+        }
+    }
+}
@@ -1,5 +1,4 @@
 use voile_util::{
-    level::Level,
     loc::Ident,
     meta::MI,
     tags::{Plicit, VarRec},
@@ -8,7 +7,7 @@ use voile_util::{
 
 use crate::syntax::{
     common::ConHead,
-    core::{Bind, Closure, Elim, Tele, Term, Val, ValData},
+    core::{Bind, Closure, Elim, Level, Tele, Term, Val, ValData},
 };
 
 pub const TYPE_OMEGA: Val = Val::Type(Level::Omega);
@@ -41,10 +40,10 @@ impl Term {
             Term::Whnf(val) => val,
             Term::Redex(..) => return false,
         } {
-            Id(..) | Type(..) | Pi(..) | Data(..) => true,
+            Id(..) | Type(..) | LevelType | Pi(..) | Data(..) => true,
             // In case it's neutral, we use `is_universe` on its type.
             // In case it's a meta, we're supposed to solve it.
-            Refl | Var(..) | Meta(..) | Cons(..) | Axiom(..) => false,
+            Refl | Lam(..) | Var(..) | Meta(..) | Cons(..) | Axiom(..) | Level(..) => false,
         }
     }
 
@@ -71,8 +70,16 @@ impl Term {
         Term::Whnf(Val::Refl)
     }
 
-    pub fn universe(level: Level) -> Self {
-        Term::Whnf(Val::Type(level))
+    pub fn universe(level: impl Into<Level>) -> Self {
+        Term::Whnf(Val::Type(level.into()))
+    }
+
+    /// A single level atom is represented by itself.
+    pub fn level(level: Level) -> Self {
+        match level {
+            Level::Max(0, mut atoms) if atoms.len() == 1 && atoms[0].0 == 0 => atoms.remove(0).1,
+            level => Term::Whnf(Val::Level(level)),
+        }
     }
 
     pub fn identity(ty: Self, a: Self, b: Self) -> Self {
@@ -123,6 +130,10 @@ impl Term {
     pub fn pi2(param: Bind<Box<Term>>, body: Closure) -> Self {
         Term::Whnf(Val::Pi(param, body))
     }
+
+    pub fn lam(param: Bind<Box<Term>>, body: Closure) -> Self {
+        Term::Whnf(Val::Lam(param, body))
+    }
 }
 
 impl Closure {
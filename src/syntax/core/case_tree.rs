@@ -0,0 +1,22 @@
+use voile_util::uid::GI;
+
+use crate::syntax::core::Term;
+
+/// Case trees, built by coverage checking.
+///
+/// The positions are indices in the argument stack (from left to right),
+/// matching a constructor replaces it with its arguments in the stack,
+/// and matching `refl` removes it.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.CompiledClause.html#CompiledClauses%27).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CaseTree {
+    /// Match an argument against the constructors.
+    Case(usize, Vec<(GI, CaseTree)>),
+    /// Match an argument against `refl`.
+    Refl(usize, Box<CaseTree>),
+    /// The body, whose context is the argument stack of the given size
+    /// (the last argument is `DBI(0)`).
+    Done(usize, Term),
+    /// Absurd case.
+    Fail,
+}
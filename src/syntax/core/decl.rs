@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 
-use voile_util::{level::Level, loc::*, uid::GI};
+use voile_util::{loc::*, uid::GI};
 
 use crate::{
     check::pats::CoreCopat,
-    syntax::core::{Tele, Term},
+    syntax::core::{CaseTree, Level, Tele, Term},
 };
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -15,7 +15,11 @@ pub struct CodataInfo {
     pub params: Tele,
     /// References to its projections (fields).
     pub fields: HashMap<String, GI>,
+    /// The universe it lives in, in the context of the parameters.
     pub level: Level,
+    /// Whether it has eta-equality, which is the case if
+    /// its projections don't mention itself.
+    pub eta: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -23,6 +27,8 @@ pub struct ProjInfo {
     pub loc: Loc,
     pub name: Ident,
     pub codata: GI,
+    /// In the context of the codata parameters and the record value `self`,
+    /// which is the innermost one.
     pub ty: Term,
 }
 
@@ -32,6 +38,8 @@ pub struct FuncInfo {
     pub name: Ident,
     pub signature: Term,
     pub clauses: Vec<Clause>,
+    /// Built by coverage checking after all the clauses are checked.
+    pub case_tree: Option<CaseTree>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -40,6 +48,9 @@ pub struct ConsInfo {
     pub name: Ident,
     pub params: Tele,
     pub data: GI,
+    /// The indices of the datatype it constructs, in the context of
+    /// the data parameters and `params`.
+    pub indices: Vec<Term>,
     /// If this is a record constructor,
     /// we fill the fields' names here.
     pub fields: Option<Vec<String>>,
@@ -50,8 +61,11 @@ pub struct DataInfo {
     pub loc: Loc,
     pub name: Ident,
     pub params: Tele,
+    /// The index telescope, in the context of the parameters.
+    pub indices: Tele,
     /// References to its constructors.
     pub conses: Vec<GI>,
+    /// The universe it lives in, in the context of the parameters.
     pub level: Level,
 }
 
@@ -101,13 +115,13 @@ impl ToLoc for Decl {
 /// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.Syntax.Internal.html#Clause).
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Clause {
+    pub source: Loc,
     /// $\Delta$. The types of the pattern variables in dependency order.
     pub pat_tele: Tele,
     /// $\Delta \vdash ps$. The de Bruijn indices refer to $\Delta$.
     pub patterns: Vec<CoreCopat>,
     /// `Some(v)` if $\Delta \vdash v$, while `None` if the patterns are absurd.
     pub body: Option<Term>,
-    // TODO: case-trees.
 }
 
 impl Clause {
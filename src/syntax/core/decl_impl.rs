@@ -1,4 +1,14 @@
-use crate::syntax::core::*;
+use std::iter::once;
+
+use voile_util::uid::GI;
+
+use crate::syntax::{
+    common::{ConHead, Ductive},
+    core::{
+        subst::{RedEx, Subst},
+        *,
+    },
+};
 
 macro_rules! simple_to_loc {
     ($name:ident) => {
@@ -16,3 +26,24 @@ simple_to_loc!(ConsInfo);
 simple_to_loc!(ProjInfo);
 simple_to_loc!(TermInfo);
 simple_to_loc!(FuncInfo);
+
+impl ConsInfo {
+    /// The head of this constructor, where `ix` is the index of the
+    /// constructor itself.
+    pub fn head(&self, ix: GI) -> ConHead {
+        let (ductive, fields) = match &self.fields {
+            Some(fields) => (Ductive::In, fields.clone()),
+            None => (Ductive::Coin, vec![]),
+        };
+        ConHead::new(self.name.clone(), ix, ductive, fields)
+    }
+}
+
+impl ProjInfo {
+    /// The type of this projection applied to `record`, which is of the
+    /// codata type applied to `params`.
+    pub fn instantiate(&self, params: Vec<Term>, record: Term) -> Term {
+        let subst = Subst::parallel(once(record).chain(params.into_iter().rev()));
+        self.ty.clone().reduce_dbi(subst)
+    }
+}
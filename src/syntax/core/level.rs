@@ -0,0 +1,190 @@
+use std::{
+    fmt::{Display, Error, Formatter},
+    rc::Rc,
+};
+
+use voile_util::level::{Level as ConstLevel, LevelType};
+
+use crate::syntax::core::{
+    subst::{RedEx, Subst},
+    Term, Val,
+};
+
+/// Universe levels, the maximum of a constant and some level atoms
+/// (neutral terms of type `Level`, like variables and metas) plus constants.
+/// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.Syntax.Internal.html#Level).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Level {
+    /// The level of the sorts of telescopes, bigger than all the others.
+    Omega,
+    /// `max(n, a0 + k0, a1 + k1, ..)`.
+    Max(LevelType, Vec<(LevelType, Term)>),
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level::Max(0, vec![])
+    }
+}
+
+impl From<ConstLevel> for Level {
+    fn from(level: ConstLevel) -> Self {
+        match level {
+            ConstLevel::Omega => Level::Omega,
+            ConstLevel::Num(n) => Level::Max(n, vec![]),
+        }
+    }
+}
+
+impl Level {
+    /// A level atom, which is supposed to be neutral.
+    pub fn atom(term: Term) -> Self {
+        Level::Max(0, vec![(0, term)]).normalize()
+    }
+
+    /// `self + n`.
+    pub fn lift(self, n: LevelType) -> Self {
+        match self {
+            Level::Omega => Level::Omega,
+            Level::Max(c, atoms) => {
+                let atoms = atoms.into_iter().map(|(k, a)| (k + n, a)).collect();
+                Level::Max(c + n, atoms).normalize()
+            }
+        }
+    }
+
+    /// `self - n`, if every component of `self` is at least `n`.
+    pub fn fall(self, n: LevelType) -> Option<Self> {
+        match self {
+            Level::Omega => Some(Level::Omega),
+            Level::Max(c, atoms) => {
+                let atoms = (atoms.into_iter())
+                    .map(|(k, a)| Some((k.checked_sub(n)?, a)))
+                    .collect::<Option<Vec<_>>>()?;
+                let c = match c.checked_sub(n) {
+                    Some(c) => c,
+                    // The constant is dominated by the atoms.
+                    None if !atoms.is_empty() => 0,
+                    None => return None,
+                };
+                Some(Level::Max(c, atoms).normalize())
+            }
+        }
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        match (self, other) {
+            (Level::Max(c, mut a), Level::Max(d, b)) => {
+                a.extend(b);
+                Level::Max(c.max(d), a).normalize()
+            }
+            _ => Level::Omega,
+        }
+    }
+
+    /// Flatten the atoms that are levels, merge the duplicated atoms
+    /// and drop the constant if it's dominated by an atom.
+    pub fn normalize(self) -> Self {
+        let (c, atoms) = match self {
+            Level::Omega => return Level::Omega,
+            Level::Max(c, atoms) => (c, atoms),
+        };
+        let mut constant = c;
+        let mut merged: Vec<(LevelType, Term)> = Vec::with_capacity(atoms.len());
+        let mut todo = atoms;
+        todo.reverse();
+        while let Some((k, atom)) = todo.pop() {
+            match atom {
+                Term::Whnf(Val::Level(Level::Omega)) => return Level::Omega,
+                Term::Whnf(Val::Level(Level::Max(d, inner))) => {
+                    constant = constant.max(d + k);
+                    todo.extend(inner.into_iter().rev().map(|(j, a)| (j + k, a)));
+                }
+                atom => match merged.iter_mut().find(|(_, a)| *a == atom) {
+                    Some((j, _)) => *j = k.max(*j),
+                    None => merged.push((k, atom)),
+                },
+            }
+        }
+        if merged.iter().any(|(k, _)| *k >= constant) {
+            constant = 0;
+        }
+        Level::Max(constant, merged)
+    }
+
+    /// The level atoms.
+    pub fn atoms(&self) -> impl Iterator<Item = &Term> {
+        let atoms = match self {
+            Level::Omega => &[][..],
+            Level::Max(_, atoms) => atoms.as_slice(),
+        };
+        atoms.iter().map(|(_, a)| a)
+    }
+
+    /// The level is a constant.
+    pub fn as_const(&self) -> Option<LevelType> {
+        match self {
+            Level::Max(c, atoms) if atoms.is_empty() => Some(*c),
+            _ => None,
+        }
+    }
+
+    /// Is `self` smaller than or equal to `other` for every instance
+    /// of the atoms?
+    /// [Agda](https://hackage.haskell.org/package/Agda-2.6.0.1/docs/src/Agda.TypeChecking.LevelConstraints.html#leqLevel).
+    pub fn leq(&self, other: &Self) -> bool {
+        let (c, a, d, b) = match (self, other) {
+            (_, Level::Omega) => return true,
+            (Level::Omega, _) => return false,
+            (Level::Max(c, a), Level::Max(d, b)) => (c, a, d, b),
+        };
+        let const_leq = |c: LevelType| c <= *d || b.iter().any(|(k, _)| c <= *k);
+        let atom_leq =
+            |(k, atom): &(LevelType, Term)| (b.iter()).any(|(j, other)| other == atom && k <= j);
+        const_leq(*c) && a.iter().all(atom_leq)
+    }
+}
+
+impl RedEx for Level {
+    fn reduce_dbi(self, subst: Rc<Subst>) -> Self {
+        match self {
+            Level::Omega => Level::Omega,
+            Level::Max(c, atoms) => {
+                let atoms = (atoms.into_iter())
+                    .map(|(k, a)| (k, a.reduce_dbi(subst.clone())))
+                    .collect();
+                Level::Max(c, atoms).normalize()
+            }
+        }
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let (c, atoms) = match self {
+            Level::Omega => return f.write_str("\u{03C9}"),
+            Level::Max(c, atoms) => (c, atoms),
+        };
+        let atom = |f: &mut Formatter, (k, a): &(LevelType, Term)| match k {
+            0 => write!(f, "{}", a),
+            k => write!(f, "{} + {}", a, k),
+        };
+        match atoms.as_slice() {
+            [] => write!(f, "{}", c),
+            [a] if *c == 0 => atom(f, a),
+            atoms => {
+                f.write_str("max(")?;
+                if *c > 0 {
+                    write!(f, "{}, ", c)?;
+                }
+                for (i, a) in atoms.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    atom(f, a)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
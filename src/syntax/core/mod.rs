@@ -1,6 +1,8 @@
 use voile_util::loc::{Loc, ToLoc};
 
-pub use self::{ast::*, ast_fold::*, ast_util::*, decl::*, decl_impl::*, pretty::*};
+pub use self::{
+    ast::*, ast_fold::*, ast_util::*, case_tree::*, decl::*, decl_impl::*, level::*, pretty::*,
+};
 
 /// Core language syntax definitions.
 mod ast;
@@ -13,10 +15,14 @@ mod ast_fold;
 ///
 /// To avoid too much `Box::new` invocations.
 mod ast_util;
+/// Case trees, the compiled form of clauses.
+mod case_tree;
 /// Checked declarations.
 mod decl;
 /// Declarations' trivial trait implementations.
 mod decl_impl;
+/// Universe levels.
+mod level;
 /// Pretty printing things.
 mod pretty;
 /// Substitution is a mapping.
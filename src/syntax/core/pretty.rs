@@ -3,9 +3,13 @@ use std::fmt::{Display, Error, Formatter};
 use voile_util::tags::{Plicit, VarRec};
 use Plicit::{Ex as Explicit, Im as Implicit};
 
-use crate::syntax::{
-    common::ConHead,
-    core::{Bind, Closure, Elim, Term, TermInfo, Val, ValData},
+use crate::{
+    check::pats::CorePat,
+    syntax::{
+        common::ConHead,
+        core::{Bind, Closure, Elim, Term, TermInfo, Val, ValData},
+        pat::Pat,
+    },
 };
 
 impl Display for Elim {
@@ -35,11 +39,18 @@ impl Display for Val {
                 pretty_application(f, mi, a)
             }
             Var(fun, a) => pretty_application(f, fun, a),
+            Type(l @ super::Level::Max(_, atoms)) if !atoms.is_empty() => write!(f, "set({})", l),
             Type(l) => write!(f, "set{}", l),
+            LevelType => f.write_str("Level"),
+            Level(l) => l.fmt(f),
             Pi(Bind { licit, ty, .. }, clos) => match licit {
                 Explicit => write!(f, "({} -> {})", ty, clos),
                 Implicit => write!(f, "({{{}}} -> {})", ty, clos),
             },
+            Lam(Bind { licit, ty, .. }, clos) => match licit {
+                Explicit => write!(f, "(\\{}. {})", ty, clos),
+                Implicit => write!(f, "(\\{{{}}}. {})", ty, clos),
+            },
             Cons(name, a) => pretty_application(f, name, a),
             Data(info) => info.fmt(f),
             Axiom(i) => write!(f, "<{}>", i),
@@ -49,6 +60,19 @@ impl Display for Val {
     }
 }
 
+/// Pattern variables are printed as `_`, as they're nameless.
+impl Display for CorePat {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            Pat::Var(_) => f.write_str("_"),
+            Pat::Refl => f.write_str("refl"),
+            Pat::Absurd => f.write_str("()"),
+            Pat::Cons(_, head, pats) => pretty_application(f, head, pats),
+            Pat::Forced(term) => write!(f, "|_{}_|", term),
+        }
+    }
+}
+
 impl Display for ValData {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         f.write_str(match self.kind {
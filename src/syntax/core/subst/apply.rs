@@ -18,6 +18,14 @@ impl Term {
                 a.append(&mut args);
                 Term::meta(m, a)
             }
+            Term::Whnf(Val::Lam(param, clos)) => {
+                let mut iter = args.into_iter();
+                match iter.next() {
+                    None => Term::lam(param, clos),
+                    Some(Elim::App(arg)) => clos.instantiate(*arg).apply_elim(iter.collect()),
+                    Some(Elim::Proj(field)) => panic!("Cannot project `{}` on a lambda.", field),
+                }
+            }
             Term::Whnf(Val::Cons(c, mut a)) => {
                 let mut iter = args.into_iter();
                 match iter.next() {
@@ -36,6 +44,7 @@ impl Term {
                 }
             }
             Term::Redex(f, id, a) => def_app(f, id, a, args),
+            e if args.is_empty() => e,
             e => panic!("Cannot eliminate `{}`.", e),
         }
     }
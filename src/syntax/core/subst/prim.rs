@@ -103,7 +103,7 @@ impl<Term: DeBruijn + RedEx<Term, Term> + Clone> PrimSubst<Term> {
             Succ(rest) => rest.lookup_impl(dbi.pred()),
             Weak(i, rest) => match &**rest {
                 IdS => Right(Term::from_dbi(dbi + *i)),
-                rho => Right(rho.lookup(*i).reduce_dbi(Self::raise(*i))),
+                rho => Right(rho.lookup(dbi).reduce_dbi(Self::raise(*i))),
             },
             Lift(n, _) if dbi < *n => Right(DeBruijn::from_dbi(dbi)),
             Lift(n, rest) => Right(Self::raise_term(*n, rest.lookup(dbi - *n))),
@@ -158,4 +158,17 @@ impl<T> PrimSubst<T> {
     pub fn one(t: T) -> Rc<Self> {
         Rc::new(PrimSubst::Cons(t, Default::default()))
     }
+
+    /// Convert the terms inside the substitution.
+    /// Used for converting pattern substitutions to term substitutions.
+    pub fn map<R>(&self, f: &impl Fn(&T) -> R) -> Rc<PrimSubst<R>> {
+        use PrimSubst::*;
+        Rc::new(match self {
+            IdS => IdS,
+            Cons(t, rest) => Cons(f(t), rest.map(f)),
+            Succ(rest) => Succ(rest.map(f)),
+            Weak(n, rest) => Weak(*n, rest.map(f)),
+            Lift(n, rest) => Lift(*n, rest.map(f)),
+        })
+    }
 }
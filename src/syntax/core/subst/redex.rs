@@ -73,8 +73,14 @@ impl RedEx<Term> for Val {
                 arg.unboxed().reduce_dbi(subst.clone()).boxed(),
                 closure.reduce_dbi(subst),
             ),
+            Val::Lam(arg, closure) => Term::lam(
+                arg.unboxed().reduce_dbi(subst.clone()).boxed(),
+                closure.reduce_dbi(subst),
+            ),
             Val::Cons(name, a) => Term::cons(name, a.reduce_dbi(subst)),
-            Val::Type(n) => Term::universe(n),
+            Val::Type(n) => Term::universe(n.reduce_dbi(subst)),
+            Val::LevelType => Term::Whnf(Val::LevelType),
+            Val::Level(l) => Term::level(l.reduce_dbi(subst)),
             Val::Data(info) => Term::data(info.reduce_dbi(subst)),
             Val::Meta(m, a) => Term::meta(m, a.reduce_dbi(subst)),
             Val::Var(f, args) => subst.lookup(f).apply_elim(args.reduce_dbi(subst)),
@@ -117,6 +123,7 @@ impl<Ix, R, T: RedEx<R>> RedEx<Copat<Ix, R>> for Copat<Ix, T> {
     fn reduce_dbi(self, subst: Rc<Subst>) -> Copat<Ix, R> {
         match self {
             Copat::App(a) => Copat::App(a.reduce_dbi(subst)),
+            Copat::Implicit(name, a) => Copat::Implicit(name, a.reduce_dbi(subst)),
             Copat::Proj(p) => Copat::Proj(p),
         }
     }
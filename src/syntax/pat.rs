@@ -1,4 +1,7 @@
-use voile_util::uid::{next_uid, UID};
+use voile_util::{
+    loc::Ident,
+    uid::{next_uid, UID},
+};
 
 use crate::syntax::common::ConHead;
 
@@ -27,6 +30,10 @@ pub enum Pat<Ix, Term> {
 pub enum Copat<Ix, Term> {
     /// Application copatterns.
     App(Pat<Ix, Term>),
+    /// Implicit application copatterns `{p}`, or `{A = p}` with the
+    /// name of the implicit parameter.
+    /// They become [`App`](Copat::App) after the implicit patterns are inserted.
+    Implicit(Option<Ident>, Pat<Ix, Term>),
     /// Projection copatterns.
     Proj(String),
 }
@@ -54,7 +61,7 @@ impl<Ix, Term> PatCommon for Pat<Ix, Term> {
 impl<Ix, Term> PatCommon for Copat<Ix, Term> {
     fn is_split(&self) -> bool {
         match self {
-            Copat::App(p) => p.is_split(),
+            Copat::App(p) | Copat::Implicit(_, p) => p.is_split(),
             // Agda panics for this case.
             Copat::Proj(..) => false,
         }
@@ -80,7 +87,7 @@ impl<Ix, Term> Copat<Ix, Term> {
 
     pub fn is_proj(&self) -> bool {
         match self {
-            Copat::App(_) => false,
+            Copat::App(_) | Copat::Implicit(..) => false,
             Copat::Proj(_) => true,
         }
     }
@@ -91,6 +98,7 @@ impl<Ix, Term> Copat<Ix, Term> {
     ) -> Copat<Ix2, Term2> {
         match self {
             Copat::App(app) => Copat::App(f(app)),
+            Copat::Implicit(name, app) => Copat::Implicit(name, f(app)),
             Copat::Proj(field) => Copat::Proj(field),
         }
     }
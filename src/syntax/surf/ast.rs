@@ -1,4 +1,5 @@
 use voile_util::{
+    level::Level,
     loc::{Ident, Labelled},
     tags::Plicit,
     vec1::Vec1,
@@ -24,18 +25,33 @@ pub struct Param {
 pub enum Expr {
     /// Variable reference
     Var(Ident),
-    /// Universe.
-    Type(Ident),
+    /// Universe, with its level.
+    Type(Ident, Level),
+    /// Reflexivity, the only constructor of identity types.
+    Refl(Ident),
+    /// The type of universe levels.
+    Level(Ident),
+    /// Successor of universe levels.
+    LSuc(Ident),
+    /// Maximum of universe levels.
+    LMax(Ident),
     /// Explicit meta variable.
     Meta(Ident),
     /// Dot-projection.
     Proj(Ident),
+    /// Implicit argument `{e}`, or `{A = e}` with the name of the parameter.
+    Implicit(Option<Ident>, Box<Self>),
     /// Application, chained.
     App(Box<Self>, Box<Vec1<Self>>),
     /// Pi-type expression, where `a -> b -> c` is represented as `Pi(vec![a,
     /// b], c)` instead of `Pi(a, Pi(b, c))`.
     /// `a` and `b` here can introduce telescopes.
     Pi(Box<Vec1<Param>>, Box<Self>),
+    /// Lambda abstraction, where `\a b. c` is represented as `Lam(vec![a,
+    /// b], c)` instead of `Lam(a, Lam(b, c))`.
+    Lam(Box<Vec1<Ident>>, Box<Self>),
+    /// Identity type `a = b`, the type of `a` and `b` is inferred.
+    Id(Box<Self>, Box<Self>),
 }
 
 impl Expr {
@@ -52,6 +68,14 @@ impl Expr {
         }
     }
 
+    pub fn lam(params: Vec1<Ident>, body: Self) -> Self {
+        Expr::Lam(Box::new(params), Box::new(body))
+    }
+
+    pub fn id(a: Self, b: Self) -> Self {
+        Expr::Id(Box::new(a), Box::new(b))
+    }
+
     pub fn app(applied: Self, arguments: Vec1<Self>) -> Self {
         Expr::App(Box::new(applied), Box::new(arguments))
     }
@@ -69,14 +93,30 @@ impl Expr {
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ExprDecl {
     Defn(Ident, Expr),
-    Cls(Ident, Vec<ExprCopat>, Expr),
-    Data(NamedTele, Vec<ExprCons>),
-    Codata(NamedTele, Vec<ExprProj>),
+    /// Absurd clauses have no body.
+    Cls(Ident, Vec<ExprCopat>, Option<Expr>),
+    /// With its indices and the universe it lives in, if specified.
+    Data(NamedTele, Option<Expr>, Vec<ExprCons>),
+    /// With the universe it lives in and its constructor, if specified.
+    Codata(NamedTele, Option<Expr>, Option<Ident>, Vec<ExprProj>),
 }
 
-pub type ExprCons = NamedTele;
 pub type ExprProj = Labelled<Expr>;
 
+/// Constructor, with its return type if specified.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ExprCons {
+    pub name: Ident,
+    pub tele: Vec<Param>,
+    pub ret: Option<Expr>,
+}
+
+impl ExprCons {
+    pub fn new(name: Ident, tele: Vec<Param>, ret: Option<Expr>) -> Self {
+        Self { name, tele, ret }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct NamedTele {
     pub name: Ident,
@@ -1,8 +1,10 @@
 use pest_derive::Parser;
 use voile_util::{
+    level::{Level, LevelType},
     loc::Ident,
     pest_util::{end_of_rule, strict_parse},
     tags::Plicit,
+    vec1::Vec1,
 };
 
 use crate::syntax::{
@@ -65,7 +67,7 @@ fn clause(rules: Tok) -> ExprDecl {
     let ident = next_ident(&mut inner);
     let (copats, expr) = next_rule!(inner, clause_body);
     end_of_rule(&mut inner);
-    ExprDecl::Cls(ident, copats, expr.unwrap())
+    ExprDecl::Cls(ident, copats, expr)
 }
 
 fn definition(rules: Tok) -> ExprDecl {
@@ -80,18 +82,33 @@ fn copattern(rules: Tok) -> ExprCopat {
     let mut inner: Tik = rules.into_inner();
     let the_rule: Tok = inner.next().unwrap();
     match the_rule.as_rule() {
+        Rule::implicit_pat => implicit_pat(the_rule),
         Rule::pattern => Copat::App(pattern(the_rule)),
         Rule::dot_projection => Copat::Proj(dot_projection(the_rule).text),
         _ => unreachable!(),
     }
 }
 
+fn implicit_pat(rules: Tok) -> ExprCopat {
+    let mut inner: Tik = rules.into_inner();
+    let the_rule: Tok = inner.next().unwrap();
+    let (name, pat) = match the_rule.as_rule() {
+        Rule::ident => (Some(ident(the_rule)), next_rule!(inner, pattern)),
+        Rule::pattern => (None, pattern(the_rule)),
+        _ => unreachable!(),
+    };
+    end_of_rule(&mut inner);
+    Copat::Implicit(name, pat)
+}
+
 fn pattern(rules: Tok) -> ExprPat {
     let mut inner: Tik = rules.into_inner();
     let the_rule: Tok = inner.next().unwrap();
     match the_rule.as_rule() {
         Rule::inacc_pat => inacc_pat(the_rule),
         Rule::cons_pat => cons_pat(the_rule),
+        Rule::refl => Pat::Refl,
+        Rule::absurd_pat => Pat::Absurd,
         Rule::ident => Pat::Var(ident(the_rule)),
         _ => unreachable!(),
     }
@@ -113,13 +130,37 @@ fn inacc_pat(rules: Tok) -> ExprPat {
 
 fn expr(rules: Tok) -> Expr {
     let mut inner: Tik = rules.into_inner();
-    let expr = next_rule!(inner, pi_expr);
+    let the_rule: Tok = inner.next().unwrap();
+    let expr = match the_rule.as_rule() {
+        Rule::lambda => lambda(the_rule),
+        Rule::pi_expr => pi_expr(the_rule),
+        e => panic!("Unexpected rule: {:?} with token {}", e, the_rule.as_str()),
+    };
     end_of_rule(&mut inner);
     expr
 }
 
+many_prefix_parser!(lambda_internal, Ident, ident, expr, Expr);
+
+fn lambda(rules: Tok) -> Expr {
+    let (mut params, body) = lambda_internal(rules);
+    let head = params.remove(0);
+    Expr::lam(Vec1::new(head, params), body.unwrap())
+}
+
 expr_parser!(dollar_expr, app_expr, app_smart);
 
+fn eq_expr(rules: Tok) -> Expr {
+    let mut inner: Tik = rules.into_inner();
+    let a = next_rule!(inner, dollar_expr);
+    let expr = match inner.next() {
+        Some(b) => Expr::id(a, dollar_expr(b)),
+        None => a,
+    };
+    end_of_rule(&mut inner);
+    expr
+}
+
 fn app_expr(rules: Tok) -> Expr {
     let mut inner: Tik = rules.into_inner();
     let fun = next_rule!(inner, primary_expr);
@@ -135,18 +176,35 @@ fn applied(rules: Tok) -> Expr {
     let the_rule: Tok = inner.next().unwrap();
     match the_rule.as_rule() {
         Rule::dot_projection => Expr::Proj(dot_projection(the_rule)),
+        Rule::implicit_arg => implicit_arg(the_rule),
         Rule::primary_expr => primary_expr(the_rule),
         _ => unreachable!(),
     }
 }
 
+fn implicit_arg(rules: Tok) -> Expr {
+    let mut inner: Tik = rules.into_inner();
+    let the_rule: Tok = inner.next().unwrap();
+    let (name, arg) = match the_rule.as_rule() {
+        Rule::ident => (Some(ident(the_rule)), next_rule!(inner, expr)),
+        Rule::expr => (None, expr(the_rule)),
+        _ => unreachable!(),
+    };
+    end_of_rule(&mut inner);
+    Expr::Implicit(name, Box::new(arg))
+}
+
 fn primary_expr(rules: Tok) -> Expr {
     let mut inner: Tik = rules.into_inner();
     let the_rule: Tok = inner.next().unwrap();
     let expr = match the_rule.as_rule() {
         Rule::ident => Expr::Var(ident(the_rule)),
         Rule::meta => Expr::Meta(meta(the_rule)),
-        Rule::universe => Expr::Type(ident(the_rule)),
+        Rule::universe => universe(the_rule),
+        Rule::refl => Expr::Refl(ident(the_rule)),
+        Rule::level_type => Expr::Level(ident(the_rule)),
+        Rule::lsuc => Expr::LSuc(ident(the_rule)),
+        Rule::lmax => Expr::LMax(ident(the_rule)),
         Rule::expr => expr(the_rule),
         e => panic!("Unexpected rule: {:?} with token {}", e, the_rule.as_str()),
     };
@@ -154,7 +212,7 @@ fn primary_expr(rules: Tok) -> Expr {
     expr
 }
 
-many_prefix_parser!(pi_expr_internal, Param, param, dollar_expr, Expr);
+many_prefix_parser!(pi_expr_internal, Param, param, eq_expr, Expr);
 many_prefix_parser!(multi_param, Ident, ident, expr, Expr);
 
 fn one_param(rules: Tok, licit: Plicit) -> Param {
@@ -180,7 +238,7 @@ fn param(rules: Tok) -> Param {
             licit: Plicit::Ex,
             names: Vec::with_capacity(0),
             ty: match rule_type {
-                Rule::dollar_expr => dollar_expr(the_rule),
+                Rule::eq_expr => eq_expr(the_rule),
                 Rule::pi_expr => pi_expr(the_rule),
                 e => panic!("Unexpected rule: {:?} with token {}", e, the_rule.as_str()),
             },
@@ -190,23 +248,54 @@ fn param(rules: Tok) -> Param {
     param
 }
 
-many_prefix_parser!(data_body, Param, param, constructors, Vec<ExprCons>);
-many_prefix_parser!(codata_body, Param, param, projections, Vec<ExprProj>);
+fn universe(rules: Tok) -> Expr {
+    let id = ident(rules.clone());
+    let mut inner: Tik = rules.into_inner();
+    let level = inner.next().map_or_else(Level::default, level);
+    end_of_rule(&mut inner);
+    Expr::Type(id, level)
+}
+
+fn level(rules: Tok) -> Level {
+    Level::Num(rules.as_str().parse::<LevelType>().unwrap())
+}
+
+fn data_universe(rules: Tok) -> Expr {
+    let mut inner: Tik = rules.into_inner();
+    let universe = next_rule!(inner, expr);
+    end_of_rule(&mut inner);
+    universe
+}
+
+/// The parameters, the universe and the body of a datatype.
+fn data_body<T>(rules: Tok, body: impl FnOnce(Tok) -> T) -> (Vec<Param>, Option<Expr>, T) {
+    let mut tele = Vec::new();
+    let mut universe = None;
+    let mut end = None;
+    for the_rule in rules.into_inner() {
+        match the_rule.as_rule() {
+            Rule::param => tele.push(param(the_rule)),
+            Rule::data_universe => universe = Some(data_universe(the_rule)),
+            _ => end = Some(the_rule),
+        }
+    }
+    (tele, universe, body(end.unwrap()))
+}
 
 fn data(rules: Tok) -> ExprDecl {
     let mut inner: Tik = rules.into_inner();
     let ident = next_ident(&mut inner);
-    let (tele, body) = next_rule!(inner, data_body);
+    let (tele, universe, body) = data_body(inner.next().unwrap(), constructors);
     end_of_rule(&mut inner);
-    ExprDecl::Data(NamedTele::new(ident, tele), body.unwrap())
+    ExprDecl::Data(NamedTele::new(ident, tele), universe, body)
 }
 
 fn codata(rules: Tok) -> ExprDecl {
     let mut inner: Tik = rules.into_inner();
     let ident = next_ident(&mut inner);
-    let (tele, body) = next_rule!(inner, codata_body);
+    let (tele, universe, (cons, body)) = data_body(inner.next().unwrap(), projections);
     end_of_rule(&mut inner);
-    ExprDecl::Codata(NamedTele::new(ident, tele), body.unwrap())
+    ExprDecl::Codata(NamedTele::new(ident, tele), universe, cons, body)
 }
 
 fn constructors(rules: Tok) -> Vec<ExprCons> {
@@ -216,11 +305,35 @@ fn constructors(rules: Tok) -> Vec<ExprCons> {
 fn constructor(rules: Tok) -> ExprCons {
     let mut inner: Tik = rules.into_inner();
     let ident = next_ident(&mut inner);
-    NamedTele::new(ident, inner.map(param).collect())
+    let mut tele = Vec::new();
+    let mut ret = None;
+    for the_rule in inner {
+        match the_rule.as_rule() {
+            Rule::param => tele.push(param(the_rule)),
+            Rule::cons_type => ret = Some(cons_type(the_rule)),
+            _ => unreachable!(),
+        }
+    }
+    ExprCons::new(ident, tele, ret)
 }
 
-fn projections(rules: Tok) -> Vec<ExprProj> {
-    rules.into_inner().map(projection).collect()
+fn cons_type(rules: Tok) -> Expr {
+    let mut inner: Tik = rules.into_inner();
+    let ty = next_rule!(inner, expr);
+    end_of_rule(&mut inner);
+    ty
+}
+
+fn projections(rules: Tok) -> (Option<Ident>, Vec<ExprProj>) {
+    let mut cons = None;
+    let mut projs = Vec::new();
+    for the_rule in rules.into_inner() {
+        match the_rule.as_rule() {
+            Rule::record_constructor => cons = Some(parse_ident(the_rule)),
+            _ => projs.push(projection(the_rule)),
+        }
+    }
+    (cons, projs)
 }
 
 fn projection(rules: Tok) -> ExprProj {
@@ -31,6 +31,8 @@ fn data_parse() {
     success!("data test tele {};");
     success!("data test tele { constructor tt tele; };");
     success!("data test (x : tele) { constructor tt (y : x); };");
+    success!("data Vec (A : Type) : Nat -> Type { constructor vnil : Vec A Zero; };");
+    success!("data test : Nat -> Type { constructor tt {n : Nat} (x : A) : test (Succ n); };");
 }
 
 #[test]
@@ -40,6 +42,38 @@ fn codata_parse() {
     success!("codata test tele {};");
     success!("codata test tele { projection tt : tele; };");
     success!("codata test (x : tele) { projection tt : (y : x) -> y; };");
+    success!("codata unit { constructor tt; };");
+    success!("codata pair { constructor mk; projection fst : a; projection snd : b; };");
+}
+
+#[test]
+fn universe_parse() {
+    use crate::syntax::surf::{parse_str_expr, Expr};
+    use voile_util::level::Level;
+    let level = |code: &str| match parse_str_expr(code).unwrap() {
+        Expr::Type(_, level) => level,
+        _ => panic!(),
+    };
+    assert_eq!(level("Type"), Level::Num(0));
+    assert_eq!(level("Type0"), Level::Num(0));
+    assert_eq!(level("Type1"), Level::Num(1));
+    assert_eq!(level("Type 42"), Level::Num(42));
+    assert!(matches!(parse_str_expr("Types").unwrap(), Expr::Var(..)));
+    success!("data wrap : Type1 { constructor wrap Type; };");
+    success!("data test (x : Type1) : Type 2 { constructor tt x; };");
+    success!("codata unit : Type1 {};");
+}
+
+#[test]
+fn level_parse() {
+    success_expr!("Level");
+    success_expr!("Type l");
+    success_expr!("Type 1 (lsuc l)");
+    success_expr!("Type (lmax a b)");
+    use crate::syntax::surf::{parse_str_expr, Expr};
+    assert!(matches!(parse_str_expr("Level").unwrap(), Expr::Level(..)));
+    assert!(matches!(parse_str_expr("lsucc").unwrap(), Expr::Var(..)));
+    success!("data List (l : Level) (A : Type l) : Type l { constructor nil; };");
 }
 
 #[test]
@@ -47,6 +81,20 @@ fn expr_parse() {
     success_expr!("Type");
     success_expr!("a b");
     success_expr!("a $ b c");
+    success_expr!("\\x. x");
+    success_expr!("\\x y. x y");
+    success_expr!("f (\\x. x) (\\y. y)");
+    success_expr!("refl");
+    success_expr!("a b = c $ d");
+    success_expr!("(n : Nat) -> plus n Zero = n");
+    success_expr!("f {Nat} a");
+    success_expr!("f {A = Vec Nat n} {b} c");
+    use crate::syntax::surf::{parse_str_expr, Expr};
+    let args = |code: &str| match parse_str_expr(code).unwrap() {
+        Expr::App(_, args) => args.len(),
+        _ => panic!(),
+    };
+    assert_eq!(args("f {A = a} b"), 2);
 }
 
 #[test]
@@ -62,4 +110,9 @@ fn clause_parse() {
     success!("clause test |_a b_| .c = b;");
     success!("clause test |_a b_| .c (c d) = b;");
     success!("clause test .e |_a b_| .c (c d) = b;");
+    success!("clause sym refl = refl;");
+    success!("clause test ();");
+    success!("clause test a (b ()) ();");
+    success!("clause test {a} b = b;");
+    success!("clause test {A = a} {(b c)} d = d;");
 }